@@ -1,12 +1,19 @@
 //! Get stats from the GitHub and crates.io API.
 
-use std::sync::LazyLock;
+use std::{
+    fmt::Display,
+    sync::LazyLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use reqwest::blocking::Client;
-use serde::{Deserialize, Serialize};
+use reqwest::{
+    blocking::{Client, Response},
+    header::{HeaderMap, AUTHORIZATION, LINK, RETRY_AFTER},
+    StatusCode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-static OWNER: &str = "sycamore-rs";
-static REPO: &str = "sycamore";
+use crate::config::SiteConfig;
 
 static CRATE: &str = "sycamore";
 
@@ -15,47 +22,313 @@ static USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_V
 static CLIENT: LazyLock<Client> = LazyLock::new(|| {
     Client::builder()
         .user_agent(USER_AGENT)
+        .timeout(Duration::from_secs(30))
         .build()
         .expect("could not create reqwest client")
 });
 
+/// Requests to the GitHub API are authenticated with this token if it is set, which raises the
+/// rate limit from 60 to 5000 requests per hour. It is only sent to [`GITHUB_API_HOST`], and not
+/// to a mock server or to any other host that a `Link` header points to.
+static GITHUB_TOKEN: LazyLock<Option<String>> = LazyLock::new(|| {
+    std::env::var("GITHUB_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+});
+
+static GITHUB_API_HOST: &str = "api.github.com";
+
+/// How many times a request is tried before giving up.
+const MAX_ATTEMPTS: u32 = 3;
+/// How long to wait before the first retry. This is doubled for every retry after that.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// If the rate limit resets later than this, give up instead of waiting for it.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
 static CACHE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/api_stats_cache.json");
 
+/// Placeholder stats that are used in offline mode when there are no cached stats, or as the
+/// starting point for a mock server. They are obviously fake so that they are never mistaken for
+/// real stats.
+static FIXTURE: &str = include_str!("../fixtures/api_stats.json");
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Cache {
+    /// When all the stats were last fetched successfully, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub fetched_at: u64,
     pub latest_release: LatestRelease,
     pub repo_stats: RepoStats,
     pub contributors: Vec<Contributor>,
     pub crates_io: CratesIo,
 }
 
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time is before the Unix epoch")
+        .as_secs()
+}
+
+#[derive(Debug)]
+pub enum StatsError {
+    /// The rate limit was exceeded. Contains how long until it resets, if known.
+    RateLimited(Option<Duration>),
+    /// The request could not be sent or the server responded with an error.
+    Http(reqwest::Error),
+    /// The response could not be decoded.
+    Decode(reqwest::Error),
+}
+
+impl Display for StatsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RateLimited(reset) => {
+                write!(f, "rate limit exceeded")?;
+                if let Some(reset) = reset {
+                    write!(f, ", resets in {}s", reset.as_secs())?;
+                }
+                if GITHUB_TOKEN.is_none() {
+                    write!(f, " (set `GITHUB_TOKEN` for a higher rate limit)")?;
+                }
+                Ok(())
+            }
+            Self::Http(err) => write!(f, "{err}"),
+            Self::Decode(err) => write!(f, "could not decode response: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for StatsError {}
+
+/// If the response says that the rate limit was exceeded, get how long until it resets.
+///
+/// GitHub responds with `403` or `429` and either sets `Retry-After` or sets
+/// `X-RateLimit-Remaining` to `0` along with `X-RateLimit-Reset`.
+fn rate_limit(status: StatusCode, headers: &HeaderMap) -> Option<Option<Duration>> {
+    let header = |name| headers.get(name)?.to_str().ok()?.parse::<u64>().ok();
+    let retry_after = header(RETRY_AFTER.as_str()).map(Duration::from_secs);
+    let exhausted = header("x-ratelimit-remaining") == Some(0);
+    let limited = status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN && (exhausted || retry_after.is_some()));
+    if !limited {
+        return None;
+    }
+    let reset = header("x-ratelimit-reset").map(|reset| Duration::from_secs(reset.saturating_sub(now())));
+    Some(retry_after.or(reset))
+}
+
+/// Whether `url` is on the GitHub API, so that the [`GITHUB_TOKEN`] can be sent to it.
+fn is_github_api(url: &str) -> bool {
+    reqwest::Url::parse(url)
+        .is_ok_and(|url| url.scheme() == "https" && url.host_str() == Some(GITHUB_API_HOST))
+}
+
+/// Send a GET request to `url`, retrying with exponential backoff if the request fails in a way
+/// that might go away by itself.
+fn get(url: &str) -> Result<Response, StatsError> {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        let mut req = CLIENT.get(url);
+        if let Some(token) = GITHUB_TOKEN.as_ref().filter(|_| is_github_api(url)) {
+            req = req.header(AUTHORIZATION, format!("Bearer {token}"));
+        }
+
+        let (err, wait) = match req.send() {
+            Ok(res) => match rate_limit(res.status(), res.headers()) {
+                // Only wait for the rate limit if it resets soon.
+                Some(Some(reset)) if reset <= MAX_RATE_LIMIT_WAIT => {
+                    (StatsError::RateLimited(Some(reset)), reset)
+                }
+                Some(reset) => return Err(StatsError::RateLimited(reset)),
+                None if res.status().is_server_error() => {
+                    (StatsError::Http(res.error_for_status().unwrap_err()), backoff)
+                }
+                None => return res.error_for_status().map_err(StatsError::Http),
+            },
+            Err(err) if err.is_connect() || err.is_timeout() => (StatsError::Http(err), backoff),
+            Err(err) => return Err(StatsError::Http(err)),
+        };
+
+        if attempt >= MAX_ATTEMPTS {
+            return Err(err);
+        }
+        eprintln!(
+            "warning: request to {url} failed ({err}), retrying in {}s",
+            wait.as_secs()
+        );
+        std::thread::sleep(wait);
+        backoff *= 2;
+        attempt += 1;
+    }
+}
+
+fn get_json<T: DeserializeOwned>(url: &str) -> Result<T, StatsError> {
+    get(url)?.json().map_err(StatsError::Decode)
+}
+
+/// Get the URL of the next page from the `Link` header of a paginated GitHub API response.
+///
+/// See <https://docs.github.com/en/rest/using-the-rest-api/using-pagination-in-the-rest-api>.
+fn next_page(res: &Response) -> Option<String> {
+    let link = res.headers().get(LINK)?.to_str().ok()?;
+    link.split(',').find_map(|link| {
+        let (url, params) = link.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == r#"rel="next""#)
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+/// Get all the pages of a paginated GitHub API endpoint.
+fn get_all_pages<T: DeserializeOwned>(url: &str) -> Result<Vec<T>, StatsError> {
+    let mut items = Vec::new();
+    let mut url = Some(url.to_string());
+    while let Some(current) = url {
+        let res = get(&current)?;
+        url = next_page(&res);
+        items.extend(res.json::<Vec<T>>().map_err(StatsError::Decode)?);
+    }
+    Ok(items)
+}
+
+/// Get the contributors of all the `repositories`, without bots and without duplicates.
+fn get_contributors_of(
+    github: &str,
+    repositories: &[String],
+) -> Result<Vec<Contributor>, StatsError> {
+    let mut contributors = Vec::<Contributor>::new();
+    for repository in repositories {
+        let url = format!("{github}/repos/{repository}/contributors?per_page=100");
+        for contributor in get_all_pages::<Contributor>(&url)? {
+            let is_bot = contributor.login.ends_with("[bot]");
+            if !is_bot && !contributors.iter().any(|c| c.login == contributor.login) {
+                contributors.push(contributor);
+            }
+        }
+    }
+    Ok(contributors)
+}
+
+/// Set `value` to the result of a request. If the request failed, `value` is left as is and
+/// `false` is returned.
+fn refresh<T>(name: &str, res: Result<T, StatsError>, value: &mut T) -> bool {
+    match res {
+        Ok(new) => {
+            *value = new;
+            true
+        }
+        Err(err) => {
+            eprintln!("warning: could not fetch {name} ({err}), keeping the previous value");
+            false
+        }
+    }
+}
+
+/// Fetch the stats from GitHub and crates.io, or from the mock server in
+/// [`SiteConfig::stats_api_url`] if it is set.
+///
+/// Every stat is fetched separately so that the ones that fail keep their values from `cache`.
+/// Also returns whether all of them were fetched.
+fn fetch(mut cache: Cache, config: &SiteConfig) -> (Cache, bool) {
+    // The repository is in the form `owner/repo`.
+    let repository = &config.repository;
+    let (github, crates_io) = match &config.stats_api_url {
+        Some(url) => (url.clone(), format!("{url}/api/v1")),
+        None => (
+            format!("https://{GITHUB_API_HOST}"),
+            "https://crates.io/api/v1".to_string(),
+        ),
+    };
+
+    let latest_release = format!("{github}/repos/{repository}/releases/latest");
+    // Contributors to the other repositories, e.g. this website, are counted as well.
+    let contributor_repositories = std::iter::once(repository)
+        .chain(&config.contributor_repositories)
+        .cloned()
+        .collect::<Vec<_>>();
+    let repo_stats = format!("{github}/repos/{repository}");
+    let crates_io = format!("{crates_io}/crates/{CRATE}");
+
+    // The blocking client cannot be used from inside of the async runtime.
+    std::thread::spawn(move || {
+        let fetched_at = now();
+        let results = [
+            refresh(
+                "latest release",
+                get_json(&latest_release),
+                &mut cache.latest_release,
+            ),
+            refresh(
+                "contributors",
+                get_contributors_of(&github, &contributor_repositories),
+                &mut cache.contributors,
+            ),
+            refresh(
+                "repository stats",
+                get_json(&repo_stats),
+                &mut cache.repo_stats,
+            ),
+            refresh(
+                "crates.io stats",
+                get_json(&crates_io),
+                &mut cache.crates_io,
+            ),
+        ];
+        // Only count the stats as fresh if all of them were fetched, so that the others are
+        // tried again on the next build.
+        let complete = results.iter().all(|ok| *ok);
+        if complete {
+            cache.fetched_at = fetched_at;
+        }
+        (cache, complete)
+    })
+    .join()
+    .unwrap()
+}
+
+fn placeholder() -> Cache {
+    serde_json::from_str(FIXTURE).expect("could not parse placeholder stats")
+}
+
 static CACHED_VALUES: LazyLock<Cache> = LazyLock::new(|| {
-    // If file does not exist, create it first.
-    if !std::path::Path::new(CACHE).exists() {
-        let latest_release = format!("https://api.github.com/repos/{OWNER}/{REPO}/releases/latest");
-        // TODO: This will no longer work if we get over 100 contributors.
-        let contributors =
-            format!("https://api.github.com/repos/{OWNER}/{REPO}/contributors?per_page=100");
-        let repo_stats = format!("https://api.github.com/repos/{OWNER}/{REPO}");
-        let crates_io = format!("https://crates.io/api/v1/crates/{CRATE}");
-
-        let values = std::thread::spawn(move || {
-            Ok::<Cache, reqwest::Error>(Cache {
-                latest_release: CLIENT.get(&latest_release).send()?.json()?,
-                contributors: CLIENT.get(&contributors).send()?.json()?,
-                repo_stats: CLIENT.get(&repo_stats).send()?.json()?,
-                crates_io: CLIENT.get(&crates_io).send()?.json()?,
-            })
-        })
-        .join()
-        .unwrap()
-        .expect("could not get values from GitHub API");
+    let config = crate::config::site_config();
+    let cached = std::fs::read_to_string(CACHE)
+        .ok()
+        .and_then(|cache| serde_json::from_str::<Cache>(&cache).ok());
 
-        let file = std::fs::File::create(CACHE).expect("could not create cache file");
-        serde_json::to_writer(file, &values).expect("could not write to cache file");
+    if config.offline {
+        return cached.unwrap_or_else(|| {
+            eprintln!("warning: not fetching stats in offline mode, using placeholder stats instead");
+            placeholder()
+        });
+    }
+
+    let max_age = config.stats_ttl * 60 * 60;
+    match cached {
+        Some(cached) if now().saturating_sub(cached.fetched_at) < max_age && !config.refresh_stats => {
+            cached
+        }
+        cached => {
+            eprintln!("Fetching stats from GitHub and crates.io");
+            // Without previous stats, the placeholder stats would be deployed as if they were real
+            // if one of the requests failed. This is fine for a mock server, but nothing else.
+            let must_complete = cached.is_none() && config.stats_api_url.is_none();
+            let (values, complete) = fetch(cached.unwrap_or_else(placeholder), config);
+            if must_complete && !complete {
+                eprintln!(
+                    "error: could not fetch the stats and there are no cached stats to fall back \
+                     on, use `--offline` to build with placeholder stats instead"
+                );
+                std::process::exit(1);
+            }
+            let file = std::fs::File::create(CACHE).expect("could not create cache file");
+            serde_json::to_writer(file, &values).expect("could not write to cache file");
+            values
+        }
     }
-    let file = std::fs::File::open(CACHE).expect("could not open cache file");
-    serde_json::from_reader(file).expect("could not parse cache file")
 });
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -103,3 +376,186 @@ pub struct Crate {
 pub fn get_crate_io_stats() -> CratesIo {
     CACHED_VALUES.crates_io.clone()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex, Once},
+    };
+
+    use super::*;
+
+    type MockRoutes = Vec<(&'static str, Option<String>, String)>;
+
+    /// The headers of every request that a mock server received, in lowercase.
+    type Requests = Arc<Mutex<Vec<Vec<String>>>>;
+
+    /// Serve the routes that `routes` returns for the URL of the server on a random port. Each
+    /// route is a path along with the `Link` header and the JSON body of its response. Any other
+    /// path responds with `404`. Returns the URL of the server and the requests it receives.
+    fn mock_server(routes: impl FnOnce(&str) -> MockRoutes) -> (String, Requests) {
+        // Set a token so that the tests would notice if it was sent to the mock server. This has
+        // to happen before any test reads it.
+        static SET_TOKEN: Once = Once::new();
+        SET_TOKEN.call_once(|| std::env::set_var("GITHUB_TOKEN", "test-token"));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let routes = routes(&url);
+        let requests = Requests::default();
+        let received = Arc::clone(&requests);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let headers = reader
+                    .lines()
+                    .map(Result::unwrap)
+                    .take_while(|line| !line.is_empty())
+                    .map(|line| line.to_lowercase())
+                    .collect();
+                received.lock().unwrap().push(headers);
+                let path = request_line.split(' ').nth(1).unwrap_or_default();
+                let response = match routes.iter().find(|(route, ..)| *route == path) {
+                    Some((_, link, body)) => {
+                        let link = link
+                            .as_ref()
+                            .map(|link| format!("Link: {link}\r\n"))
+                            .unwrap_or_default();
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n{link}\
+                             Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                            body.len()
+                        )
+                    }
+                    None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string(),
+                };
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn contributors(logins: &[&str]) -> String {
+        let contributors = logins
+            .iter()
+            .map(|login| Contributor {
+                login: login.to_string(),
+                avatar_url: format!("https://example.com/{login}.png"),
+                html_url: format!("https://example.com/{login}"),
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_string(&contributors).unwrap()
+    }
+
+    fn config(stats_api_url: String) -> SiteConfig {
+        SiteConfig {
+            stats_api_url: Some(stats_api_url),
+            contributor_repositories: vec!["sycamore-rs/website".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fetch_from_mock_server() {
+        let (url, requests) = mock_server(|url| {
+            let next = format!(r#"<{url}/repositories/1/contributors?page=2>; rel="next""#);
+            vec![
+            (
+                "/repos/sycamore-rs/sycamore/releases/latest",
+                None,
+                r#"{"html_url": "https://example.com/release", "name": "v9.9.9"}"#.to_string(),
+            ),
+            (
+                "/repos/sycamore-rs/sycamore/contributors?per_page=100",
+                Some(next),
+                contributors(&["alice", "dependabot[bot]"]),
+            ),
+            (
+                "/repositories/1/contributors?page=2",
+                None,
+                contributors(&["bob"]),
+            ),
+            (
+                "/repos/sycamore-rs/website/contributors?per_page=100",
+                None,
+                contributors(&["bob", "carol"]),
+            ),
+            (
+                "/repos/sycamore-rs/sycamore",
+                None,
+                r#"{"stargazers_count": 1234}"#.to_string(),
+            ),
+            (
+                "/api/v1/crates/sycamore",
+                None,
+                r#"{"crate": {"downloads": 42000}}"#.to_string(),
+            ),
+            ]
+        });
+
+        let (stats, complete) = fetch(placeholder(), &config(url.clone()));
+        assert!(complete);
+        assert_ne!(stats.fetched_at, 0);
+        assert_eq!(stats.latest_release.name, "v9.9.9");
+        assert_eq!(stats.repo_stats.stargazers_count, 1234);
+        assert_eq!(stats.crates_io._crate.downloads, 42000);
+        let logins = stats
+            .contributors
+            .iter()
+            .map(|contributor| contributor.login.as_str())
+            .collect::<Vec<_>>();
+        // Bots are skipped, all the pages are fetched and contributors are only counted once.
+        assert_eq!(logins, ["alice", "bob", "carol"]);
+
+        // The token is only for GitHub, not for a mock server or the pages that it links to.
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 6);
+        assert!(requests
+            .iter()
+            .flatten()
+            .all(|header| !header.starts_with("authorization:")));
+    }
+
+    #[test]
+    fn token_is_only_sent_to_github() {
+        assert!(is_github_api("https://api.github.com/repos/sycamore-rs/sycamore"));
+        assert!(!is_github_api("http://api.github.com/repos/sycamore-rs/sycamore"));
+        assert!(!is_github_api("https://api.github.com.example.com/repos"));
+        assert!(!is_github_api("https://example.com/api.github.com"));
+        assert!(!is_github_api("http://127.0.0.1:8080/repos/sycamore-rs/sycamore"));
+        assert!(!is_github_api("https://crates.io/api/v1/crates/sycamore"));
+    }
+
+    #[test]
+    fn failed_stats_keep_previous_values() {
+        let (url, _) = mock_server(|_| {
+            vec![(
+                "/repos/sycamore-rs/sycamore",
+                None,
+                r#"{"stargazers_count": 1234}"#.to_string(),
+            )]
+        });
+        let mut previous = placeholder();
+        previous.crates_io._crate.downloads = 7;
+
+        let (stats, complete) = fetch(previous, &config(url));
+        assert!(!complete);
+        assert_eq!(stats.fetched_at, 0);
+        assert_eq!(stats.repo_stats.stargazers_count, 1234);
+        assert_eq!(stats.crates_io._crate.downloads, 7);
+    }
+
+    #[test]
+    fn placeholder_is_obviously_fake() {
+        let stats = placeholder();
+        assert_eq!(stats.repo_stats.stargazers_count, 0);
+        assert_eq!(stats.crates_io._crate.downloads, 0);
+        assert!(stats.contributors.is_empty());
+    }
+}
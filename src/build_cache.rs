@@ -0,0 +1,396 @@
+//! Incremental rebuilds.
+//!
+//! Trunk clears the output directory before every build, so we keep a copy of everything that we
+//! generate in `target/ssr_cache`, along with a manifest of the inputs that each output was built
+//! from. On the next build, outputs whose inputs have not changed are copied back instead of being
+//! built again.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{site_config, SiteConfig};
+
+static CACHE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/ssr_cache");
+
+/// Hash the inputs of an output. The hash is only stable for a single build of the binary, which
+/// is fine since the cache is invalidated whenever the binary changes anyway.
+pub fn fingerprint(inputs: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    inputs.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hash the configuration of the site. This includes the fields that are not embedded into the
+/// pages, e.g. [`SiteConfig::docs_dir`], since they change what is rendered as well.
+fn config_fingerprint(config: &SiteConfig) -> u64 {
+    // Destructure the config so that new fields cannot be forgotten here.
+    let SiteConfig {
+        docs_dir,
+        // The outputs are copied to wherever the site is written to.
+        output_dir: _,
+        base_url,
+        repository,
+        contributor_repositories,
+        discord,
+        analytics_id,
+        offline,
+        stats_api_url,
+        stats_ttl,
+        // Refreshing the stats does not change anything by itself, and the stats are part of the
+        // inputs of the pages that show them.
+        refresh_stats: _,
+        drafts,
+    } = config;
+    fingerprint((
+        docs_dir,
+        base_url,
+        repository,
+        contributor_repositories,
+        discord,
+        analytics_id,
+        offline,
+        stats_api_url,
+        stats_ttl,
+        drafts,
+    ))
+}
+
+/// A fingerprint of the binary itself and the configuration of the site. If either changes, all
+/// the outputs need to be rebuilt.
+fn binary_version() -> String {
+    let metadata = std::env::current_exe().and_then(fs::metadata).ok();
+    let modified = metadata.as_ref().and_then(|metadata| metadata.modified().ok());
+    let len = metadata.map(|metadata| metadata.len());
+    format!(
+        "{}-{:016x}",
+        env!("CARGO_PKG_VERSION"),
+        fingerprint((modified, len, config_fingerprint(site_config())))
+    )
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    /// The version of the binary that built the outputs.
+    version: String,
+    /// A map from the key of each entry, e.g. the path of a page, to how it was built.
+    entries: BTreeMap<String, Entry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    /// The fingerprint of the inputs.
+    inputs: String,
+    /// The paths of all the files that were produced, relative to the public path.
+    outputs: Vec<String>,
+}
+
+/// Keeps track of which outputs can be reused from the previous build.
+pub struct BuildCache {
+    /// Where the copies of the outputs and the manifest are kept, i.e. [`CACHE_DIR`].
+    dir: PathBuf,
+    public_path: PathBuf,
+    /// The manifest of the previous build, if it was built by the same binary.
+    old: Manifest,
+    new: Manifest,
+}
+
+impl BuildCache {
+    fn manifest_path(&self) -> PathBuf {
+        self.dir.join("manifest.json")
+    }
+
+    fn cache_path(&self, output: &str) -> PathBuf {
+        self.dir.join("out").join(output.trim_start_matches('/'))
+    }
+
+    /// Load the manifest of the previous build.
+    pub fn load(public_path: &Path) -> Self {
+        Self::load_from(Path::new(CACHE_DIR), public_path, binary_version())
+    }
+
+    /// Load the manifest of the previous build from the cache in `dir`. Nothing is reused if the
+    /// previous build had a different `version`.
+    fn load_from(dir: &Path, public_path: &Path, version: String) -> Self {
+        let mut old = fs::read_to_string(dir.join("manifest.json"))
+            .ok()
+            .and_then(|manifest| serde_json::from_str::<Manifest>(&manifest).ok())
+            .unwrap_or_default();
+        if old.version != version {
+            if !old.version.is_empty() {
+                eprintln!(
+                    "The binary or the config has changed since the last build, rendering everything"
+                );
+            }
+            // Nothing can be reused, but keep track of the old outputs so that they can be
+            // cleaned up.
+            for entry in old.entries.values_mut() {
+                entry.inputs.clear();
+            }
+        }
+        Self {
+            dir: dir.to_path_buf(),
+            public_path: public_path.to_path_buf(),
+            old,
+            new: Manifest {
+                version,
+                entries: BTreeMap::new(),
+            },
+        }
+    }
+
+    /// If the outputs of `key` were built from the same `inputs` in the previous build, copy them
+    /// to the public path and return `true`. Otherwise, the outputs need to be built again.
+    pub fn restore(&mut self, key: &str, inputs: u64) -> bool {
+        let inputs = format!("{inputs:016x}");
+        let Some(entry) = self.old.entries.get(key).filter(|entry| entry.inputs == inputs) else {
+            return false;
+        };
+        if !entry
+            .outputs
+            .iter()
+            .all(|output| self.cache_path(output).is_file())
+        {
+            return false;
+        }
+        for output in &entry.outputs {
+            let path = self.public_path.join(output.trim_start_matches('/'));
+            let dir = path.parent().expect("failed to get parent dir");
+            fs::create_dir_all(dir).expect("failed to create parent dir");
+            fs::copy(self.cache_path(output), path).expect("failed to restore cached output");
+        }
+        self.new.entries.insert(key.to_string(), entry.clone());
+        true
+    }
+
+    /// Save the `outputs` of `key` in the cache after they have been written to the public path.
+    pub fn record(&mut self, key: &str, inputs: u64, outputs: Vec<String>) {
+        for output in &outputs {
+            let path = self.cache_path(output);
+            let dir = path.parent().expect("failed to get parent dir");
+            fs::create_dir_all(dir).expect("failed to create cache dir");
+            fs::copy(self.public_path.join(output.trim_start_matches('/')), path)
+                .expect("failed to cache output");
+        }
+        let inputs = format!("{inputs:016x}");
+        self.new
+            .entries
+            .insert(key.to_string(), Entry { inputs, outputs });
+    }
+
+    /// All the outputs of this build so far, both restored and recorded ones.
+    pub fn outputs(&self) -> impl Iterator<Item = &str> {
+        self.new
+            .entries
+            .values()
+            .flat_map(|entry| entry.outputs.iter().map(String::as_str))
+    }
+
+    /// Delete the outputs of the previous build that were not produced again, e.g. because their
+    /// source was removed, and write the new manifest.
+    pub fn finish(self) {
+        // The same output, e.g. a server component, can be produced by more than one entry.
+        let outputs = self
+            .new
+            .entries
+            .values()
+            .flat_map(|entry| &entry.outputs)
+            .collect::<BTreeSet<_>>();
+        for entry in self.old.entries.values() {
+            for output in &entry.outputs {
+                if outputs.contains(output) {
+                    continue;
+                }
+                eprintln!("Removing stale output `{output}`");
+                let _ = fs::remove_file(self.cache_path(output));
+                let _ = fs::remove_file(self.public_path.join(output.trim_start_matches('/')));
+            }
+        }
+
+        fs::create_dir_all(&self.dir).expect("failed to create cache dir");
+        let manifest = serde_json::to_string_pretty(&self.new).expect("failed to serialize manifest");
+        fs::write(self.manifest_path(), manifest).expect("failed to write build manifest");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// A cache and a public path in temporary directories.
+    struct Site {
+        cache: TempDir,
+        public: TempDir,
+    }
+
+    impl Site {
+        fn new() -> Self {
+            Self {
+                cache: tempfile::tempdir().unwrap(),
+                public: tempfile::tempdir().unwrap(),
+            }
+        }
+
+        /// Start a new build. Like trunk, this clears the public path first.
+        fn build(&self, version: &str) -> BuildCache {
+            fs::remove_dir_all(self.public.path()).unwrap();
+            fs::create_dir_all(self.public.path()).unwrap();
+            BuildCache::load_from(self.cache.path(), self.public.path(), version.to_string())
+        }
+
+        /// Write `output` to the public path and record it in `cache`.
+        fn render(&self, cache: &mut BuildCache, key: &str, inputs: u64, output: &str) {
+            let path = self.public.path().join(output.trim_start_matches('/'));
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, format!("{key} {inputs}")).unwrap();
+            cache.record(key, inputs, vec![output.to_string()]);
+        }
+
+        fn read(&self, output: &str) -> Option<String> {
+            fs::read_to_string(self.public.path().join(output.trim_start_matches('/'))).ok()
+        }
+    }
+
+    #[test]
+    fn fingerprints_depend_on_the_inputs() {
+        assert_eq!(fingerprint(("a", 1)), fingerprint(("a", 1)));
+        assert_ne!(fingerprint(("a", 1)), fingerprint(("a", 2)));
+        assert_ne!(fingerprint(("ab", "c")), fingerprint(("a", "bc")));
+    }
+
+    #[test]
+    fn unchanged_outputs_are_restored() {
+        let site = Site::new();
+        let mut cache = site.build("v1");
+        assert!(!cache.restore("/post/a", 1));
+        site.render(&mut cache, "/post/a", 1, "/post/a.html");
+        cache.finish();
+
+        let mut cache = site.build("v1");
+        assert_eq!(site.read("/post/a.html"), None);
+        assert!(cache.restore("/post/a", 1));
+        assert_eq!(site.read("/post/a.html").as_deref(), Some("/post/a 1"));
+        assert_eq!(cache.outputs().collect::<Vec<_>>(), ["/post/a.html"]);
+    }
+
+    #[test]
+    fn changed_inputs_are_rendered_again() {
+        let site = Site::new();
+        let mut cache = site.build("v1");
+        site.render(&mut cache, "/post/a", 1, "/post/a.html");
+        cache.finish();
+
+        let mut cache = site.build("v1");
+        assert!(!cache.restore("/post/a", 2));
+        site.render(&mut cache, "/post/a", 2, "/post/a.html");
+        cache.finish();
+
+        // The new output replaces the old one in the cache.
+        let mut cache = site.build("v1");
+        assert!(!cache.restore("/post/a", 1));
+        assert!(cache.restore("/post/a", 2));
+        assert_eq!(site.read("/post/a.html").as_deref(), Some("/post/a 2"));
+    }
+
+    #[test]
+    fn changed_version_renders_everything_again() {
+        let site = Site::new();
+        let mut cache = site.build("v1");
+        site.render(&mut cache, "/post/a", 1, "/post/a.html");
+        cache.finish();
+
+        let mut cache = site.build("v2");
+        assert!(!cache.restore("/post/a", 1));
+    }
+
+    #[test]
+    fn missing_cached_outputs_are_rendered_again() {
+        let site = Site::new();
+        let mut cache = site.build("v1");
+        site.render(&mut cache, "/post/a", 1, "/post/a.html");
+        cache.finish();
+        fs::remove_file(site.cache.path().join("out/post/a.html")).unwrap();
+
+        let mut cache = site.build("v1");
+        assert!(!cache.restore("/post/a", 1));
+    }
+
+    #[test]
+    fn stale_outputs_are_removed() {
+        let site = Site::new();
+        let mut cache = site.build("v1");
+        site.render(&mut cache, "/post/a", 1, "/post/a.html");
+        site.render(&mut cache, "/post/b", 1, "/post/b.html");
+        cache.finish();
+
+        // `b` was removed, and `a` was restored.
+        let mut cache = site.build("v1");
+        assert!(cache.restore("/post/a", 1));
+        // Simulate an output that was left behind in the public path.
+        fs::write(site.public.path().join("post/b.html"), "stale").unwrap();
+        cache.finish();
+
+        assert_eq!(site.read("/post/b.html"), None);
+        assert!(!site.cache.path().join("out/post/b.html").exists());
+        assert!(site.cache.path().join("out/post/a.html").exists());
+
+        let mut cache = site.build("v1");
+        assert!(!cache.restore("/post/b", 1));
+    }
+
+    #[test]
+    fn stale_outputs_are_removed_after_the_version_changes() {
+        let site = Site::new();
+        let mut cache = site.build("v1");
+        site.render(&mut cache, "/post/a", 1, "/post/a.html");
+        site.render(&mut cache, "/post/b", 1, "/post/b.html");
+        cache.finish();
+
+        let mut cache = site.build("v2");
+        site.render(&mut cache, "/post/a", 1, "/post/a.html");
+        cache.finish();
+
+        assert!(site.cache.path().join("out/post/a.html").exists());
+        assert!(!site.cache.path().join("out/post/b.html").exists());
+    }
+
+    #[test]
+    fn config_fingerprint_includes_the_fields_that_are_not_embedded() {
+        let config = SiteConfig::default();
+        let changed = [
+            SiteConfig {
+                docs_dir: "other/docs".into(),
+                ..SiteConfig::default()
+            },
+            SiteConfig {
+                drafts: true,
+                ..SiteConfig::default()
+            },
+            SiteConfig {
+                contributor_repositories: vec!["sycamore-rs/website".to_string()],
+                ..SiteConfig::default()
+            },
+            SiteConfig {
+                base_url: "http://localhost:8080".to_string(),
+                ..SiteConfig::default()
+            },
+        ];
+        for other in &changed {
+            assert_ne!(config_fingerprint(&config), config_fingerprint(other));
+        }
+
+        let unchanged = SiteConfig {
+            output_dir: "other/dist".into(),
+            refresh_stats: true,
+            ..SiteConfig::default()
+        };
+        assert_eq!(config_fingerprint(&config), config_fingerprint(&unchanged));
+    }
+}
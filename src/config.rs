@@ -0,0 +1,457 @@
+//! Configuration of the site.
+//!
+//! During SSR, the configuration is read from `site.toml`, environment variables and command line
+//! flags, in increasing order of precedence. It is then embedded into every page so that the
+//! client uses the same values.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use sycamore::prelude::{cfg_not_ssr, cfg_ssr};
+
+/// The id of the `<script>` tag that the configuration is embedded in.
+pub static SITE_CONFIG_ID: &str = "site-config";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SiteConfig {
+    /// The directory that contains the versions of the book and the blog posts.
+    #[serde(skip_serializing)]
+    pub docs_dir: PathBuf,
+    /// The directory that the site is written to.
+    #[serde(skip_serializing)]
+    pub output_dir: PathBuf,
+    /// The URL at which the site is deployed, without a trailing slash.
+    pub base_url: String,
+    /// The GitHub repository of Sycamore, e.g. `sycamore-rs/sycamore`.
+    pub repository: String,
+    /// Other GitHub repositories whose contributors are shown on the index page along with the
+    /// contributors of [`repository`](Self::repository).
+    #[serde(skip_serializing)]
+    pub contributor_repositories: Vec<String>,
+    /// The invite link of the Discord server.
+    pub discord: String,
+    /// The website id for analytics. If this is not set, no analytics script is included.
+    pub analytics_id: Option<String>,
+    /// Do not fetch the stats on the index page from GitHub and crates.io. The stats from the last
+    /// build are used if there are any, otherwise placeholder stats are used.
+    #[serde(skip_serializing)]
+    pub offline: bool,
+    /// Fetch the stats from this URL instead of from GitHub and crates.io, e.g. to use a mock
+    /// server in tests. The server should serve the same paths as `api.github.com` and
+    /// `crates.io`.
+    #[serde(skip_serializing)]
+    pub stats_api_url: Option<String>,
+    /// How long the stats are cached for, in hours.
+    #[serde(skip_serializing)]
+    pub stats_ttl: u64,
+    /// Fetch the stats again even if the cached stats have not expired yet.
+    #[serde(skip)]
+    pub refresh_stats: bool,
+    /// Render the posts that are marked as drafts.
+    #[serde(skip_serializing)]
+    pub drafts: bool,
+}
+
+impl Default for SiteConfig {
+    fn default() -> Self {
+        Self {
+            docs_dir: "sycamore/docs/".into(),
+            output_dir: "dist/.stage".into(),
+            base_url: "https://sycamore.dev".to_string(),
+            repository: "sycamore-rs/sycamore".to_string(),
+            contributor_repositories: Vec::new(),
+            discord: "https://discord.gg/vDwFUmm6mU".to_string(),
+            analytics_id: Some("e539dd59-791b-44a2-9df4-18db88d9cb80".to_string()),
+            offline: false,
+            stats_api_url: None,
+            stats_ttl: 24,
+            refresh_stats: false,
+            drafts: false,
+        }
+    }
+}
+
+impl SiteConfig {
+    /// The URL of the repository on GitHub.
+    pub fn repository_url(&self) -> String {
+        format!("https://github.com/{}", self.repository)
+    }
+
+    /// Serialize the parts of the configuration that the client needs, so that they can be
+    /// embedded into a `<script>` tag.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self)
+            .expect("failed to serialize site config")
+            // Make sure that the JSON cannot close the script tag.
+            .replace("</", "<\\/")
+    }
+}
+
+#[cfg_ssr]
+pub use self::ssr::*;
+
+#[cfg_ssr]
+mod ssr {
+    use std::{
+        fmt::Display,
+        fs, io,
+        path::{Path, PathBuf},
+        sync::OnceLock,
+    };
+
+    use super::SiteConfig;
+
+    static SITE_CONFIG: OnceLock<SiteConfig> = OnceLock::new();
+
+    /// The config file that is used if `--config` is not passed.
+    static DEFAULT_CONFIG_FILE: &str = "site.toml";
+
+    /// Get the configuration of the site. [`init`] must be called first.
+    pub fn site_config() -> &'static SiteConfig {
+        SITE_CONFIG.get().expect("site config was not initialized")
+    }
+
+    /// Set the configuration that is returned by [`site_config`].
+    pub fn init(config: SiteConfig) {
+        SITE_CONFIG
+            .set(config)
+            .expect("site config was already initialized");
+    }
+
+    /// The usage of the command line flags.
+    pub static USAGE: &str = "\
+usage: sycamore-website [check] [options]
+
+options:
+    --config <file>        the config file to use (default: site.toml)
+    --docs-dir <dir>       the directory that contains the docs and posts
+    --output-dir <dir>     the directory that the site is written to
+    --base-url <url>       the URL at which the site is deployed
+    --repository <repo>    the GitHub repository of Sycamore, e.g. sycamore-rs/sycamore
+    --contributor-repositories <repos>
+                           other repositories to show the contributors of, separated by commas
+    --discord <url>        the invite link of the Discord server
+    --analytics-id <id>    the website id for analytics, or an empty string to disable analytics
+    --offline              do not fetch the stats from GitHub and crates.io
+    --stats-api-url <url>  fetch the stats from this server instead, e.g. a mock server
+    --stats-ttl <hours>    how long the stats are cached for (default: 24)
+    --refresh-stats        fetch the stats even if the cached stats have not expired
+    --drafts               render the posts that are marked as drafts";
+
+    #[derive(Debug)]
+    pub enum ConfigError {
+        Io(PathBuf, io::Error),
+        Parse(PathBuf, Box<toml::de::Error>),
+        UnknownFlag(String),
+        MissingValue(String),
+        InvalidValue(String, String),
+        UnexpectedArgument(String),
+    }
+
+    impl Display for ConfigError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Self::Io(path, err) => write!(f, "could not read `{}`: {err}", path.display()),
+                Self::Parse(path, err) => write!(f, "could not parse `{}`: {err}", path.display()),
+                Self::UnknownFlag(flag) => write!(f, "unknown flag `{flag}`"),
+                Self::MissingValue(flag) => write!(f, "missing value for `{flag}`"),
+                Self::InvalidValue(flag, value) => {
+                    write!(f, "invalid value `{value}` for `{flag}`")
+                }
+                Self::UnexpectedArgument(arg) => write!(f, "unexpected argument `{arg}`"),
+            }
+        }
+    }
+
+    /// The command that was passed on the command line, along with the configuration.
+    #[derive(Debug)]
+    pub struct Cli {
+        pub command: Option<String>,
+        pub config: SiteConfig,
+    }
+
+    impl Cli {
+        /// Parse the command line arguments, excluding the name of the binary, and load the
+        /// configuration.
+        pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, ConfigError> {
+            Self::parse_with_env(args, |name| std::env::var(name).ok())
+        }
+
+        /// Like [`Cli::parse`], but with the environment variables from `env`.
+        fn parse_with_env(
+            args: impl IntoIterator<Item = String>,
+            env: impl Fn(&str) -> Option<String>,
+        ) -> Result<Self, ConfigError> {
+            let mut command = None;
+            let mut config_file = None;
+            let mut flags = Vec::new();
+
+            let mut args = args.into_iter();
+            while let Some(arg) = args.next() {
+                let Some(flag) = arg.strip_prefix("--") else {
+                    if command.is_some() {
+                        return Err(ConfigError::UnexpectedArgument(arg));
+                    }
+                    command = Some(arg);
+                    continue;
+                };
+                // Check the flag before taking the next argument as its value.
+                let name = flag.split_once('=').map_or(flag, |(name, _)| name);
+                if name != "config" && !FLAGS.iter().any(|(known, _)| *known == name) {
+                    return Err(ConfigError::UnknownFlag(format!("--{name}")));
+                }
+                // Accept both `--flag value` and `--flag=value`.
+                let (flag, value) = match flag.split_once('=') {
+                    Some((flag, value)) => (flag.to_string(), value.to_string()),
+                    // Switches do not take a value.
+                    None if SWITCHES.contains(&flag) => (flag.to_string(), "true".to_string()),
+                    None => {
+                        let value = args
+                            .next()
+                            .ok_or_else(|| ConfigError::MissingValue(arg.clone()))?;
+                        (flag.to_string(), value)
+                    }
+                };
+                if flag == "config" {
+                    config_file = Some(PathBuf::from(value));
+                } else {
+                    flags.push((flag, value));
+                }
+            }
+
+            let mut config = match config_file {
+                Some(path) => read_config_file(&path)?,
+                // The default config file is optional.
+                None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                    read_config_file(Path::new(DEFAULT_CONFIG_FILE))?
+                }
+                None => SiteConfig::default(),
+            };
+
+            for (flag, var) in FLAGS {
+                if let Some(value) = env(var) {
+                    set(&mut config, flag, value)?;
+                }
+            }
+            for (flag, value) in flags {
+                set(&mut config, &flag, value)?;
+            }
+            config
+                .base_url
+                .truncate(config.base_url.trim_end_matches('/').len());
+            config.analytics_id = config.analytics_id.filter(|id| !id.is_empty());
+            if let Some(url) = &mut config.stats_api_url {
+                url.truncate(url.trim_end_matches('/').len());
+            }
+
+            Ok(Self { command, config })
+        }
+    }
+
+    /// The command line flags and the environment variables that override each field.
+    static FLAGS: &[(&str, &str)] = &[
+        ("docs-dir", "SITE_DOCS_DIR"),
+        ("output-dir", "SITE_OUTPUT_DIR"),
+        ("base-url", "SITE_BASE_URL"),
+        ("repository", "SITE_REPOSITORY"),
+        ("contributor-repositories", "SITE_CONTRIBUTOR_REPOSITORIES"),
+        ("discord", "SITE_DISCORD"),
+        ("analytics-id", "SITE_ANALYTICS_ID"),
+        ("offline", "SITE_OFFLINE"),
+        ("stats-api-url", "SITE_STATS_API_URL"),
+        ("stats-ttl", "SITE_STATS_TTL"),
+        ("refresh-stats", "SITE_REFRESH_STATS"),
+        ("drafts", "SITE_DRAFTS"),
+    ];
+
+    /// The flags that are either set or not and do not take a value.
+    static SWITCHES: &[&str] = &["offline", "refresh-stats", "drafts"];
+
+    /// Set the field of `config` for `flag`.
+    fn set(config: &mut SiteConfig, flag: &str, value: String) -> Result<(), ConfigError> {
+        let switch = |value: String| match value.as_str() {
+            "true" | "1" => Ok(true),
+            "false" | "0" | "" => Ok(false),
+            _ => Err(ConfigError::InvalidValue(format!("--{flag}"), value)),
+        };
+        match flag {
+            "docs-dir" => config.docs_dir = value.into(),
+            "output-dir" => config.output_dir = value.into(),
+            "base-url" => config.base_url = value,
+            "repository" => config.repository = value,
+            "contributor-repositories" => {
+                config.contributor_repositories = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|repository| !repository.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+            "discord" => config.discord = value,
+            "analytics-id" => config.analytics_id = Some(value),
+            "offline" => config.offline = switch(value)?,
+            "stats-api-url" => config.stats_api_url = Some(value).filter(|url| !url.is_empty()),
+            "stats-ttl" => {
+                config.stats_ttl = value
+                    .parse()
+                    .map_err(|_| ConfigError::InvalidValue(format!("--{flag}"), value))?;
+            }
+            "refresh-stats" => config.refresh_stats = switch(value)?,
+            "drafts" => config.drafts = switch(value)?,
+            _ => return Err(ConfigError::UnknownFlag(format!("--{flag}"))),
+        }
+        Ok(())
+    }
+
+    fn read_config_file(path: &Path) -> Result<SiteConfig, ConfigError> {
+        let toml = fs::read_to_string(path).map_err(|err| ConfigError::Io(path.into(), err))?;
+        toml::from_str(&toml).map_err(|err| ConfigError::Parse(path.into(), Box::new(err)))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::collections::HashMap;
+
+        use tempfile::NamedTempFile;
+
+        use super::*;
+
+        fn config_file(toml: &str) -> NamedTempFile {
+            let file = NamedTempFile::new().unwrap();
+            fs::write(file.path(), toml).unwrap();
+            file
+        }
+
+        /// Parse `args` with only the environment variables in `env` set.
+        fn parse(args: &[&str], env: &[(&str, &str)]) -> Result<Cli, ConfigError> {
+            let env = env
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>();
+            Cli::parse_with_env(args.iter().map(ToString::to_string), |name| {
+                env.get(name).cloned()
+            })
+        }
+
+        #[test]
+        fn flags_override_env_and_env_overrides_file() {
+            let file = config_file(
+                r#"
+                base_url = "https://file.example.com"
+                repository = "file/repo"
+                discord = "https://discord.gg/file"
+                "#,
+            );
+            let path = file.path().to_str().unwrap();
+            let env = [
+                ("SITE_BASE_URL", "https://env.example.com"),
+                ("SITE_REPOSITORY", "env/repo"),
+            ];
+
+            let cli = parse(
+                &["--config", path, "--base-url", "https://flag.example.com"],
+                &env,
+            )
+            .unwrap();
+            assert_eq!(cli.config.base_url, "https://flag.example.com");
+            assert_eq!(cli.config.repository, "env/repo");
+            assert_eq!(cli.config.discord, "https://discord.gg/file");
+            // Everything else keeps its default.
+            assert_eq!(cli.config.stats_ttl, SiteConfig::default().stats_ttl);
+        }
+
+        #[test]
+        fn switches_and_values() {
+            let file = config_file("");
+            let path = file.path().to_str().unwrap();
+            let env = [("SITE_OFFLINE", "1"), ("SITE_DRAFTS", "true")];
+
+            let cli = parse(
+                &[
+                    "check",
+                    "--config",
+                    path,
+                    "--drafts=false",
+                    "--stats-ttl=2",
+                    "--contributor-repositories",
+                    "a/b, c/d,",
+                    "--base-url",
+                    "http://localhost:8080/",
+                    "--analytics-id",
+                    "",
+                ],
+                &env,
+            )
+            .unwrap();
+            assert_eq!(cli.command.as_deref(), Some("check"));
+            assert!(cli.config.offline);
+            assert!(!cli.config.drafts);
+            assert_eq!(cli.config.stats_ttl, 2);
+            assert_eq!(cli.config.contributor_repositories, ["a/b", "c/d"]);
+            assert_eq!(cli.config.base_url, "http://localhost:8080");
+            assert_eq!(cli.config.analytics_id, None);
+        }
+
+        #[test]
+        fn unknown_flags_are_rejected() {
+            let err = parse(&["--unknown"], &[]).unwrap_err();
+            assert!(matches!(err, ConfigError::UnknownFlag(flag) if flag == "--unknown"));
+            let err = parse(&["--unknown=1"], &[]).unwrap_err();
+            assert!(matches!(err, ConfigError::UnknownFlag(flag) if flag == "--unknown"));
+            // The command is not taken as the value of the unknown flag.
+            let err = parse(&["--unknown", "check"], &[]).unwrap_err();
+            assert!(matches!(err, ConfigError::UnknownFlag(flag) if flag == "--unknown"));
+        }
+
+        #[test]
+        fn invalid_arguments_are_rejected() {
+            let err = parse(&["--base-url"], &[]).unwrap_err();
+            assert!(matches!(err, ConfigError::MissingValue(flag) if flag == "--base-url"));
+            let err = parse(&["--stats-ttl", "soon"], &[]).unwrap_err();
+            assert!(matches!(err, ConfigError::InvalidValue(flag, value)
+                if flag == "--stats-ttl" && value == "soon"));
+            let err = parse(&["--drafts=maybe"], &[]).unwrap_err();
+            assert!(matches!(err, ConfigError::InvalidValue(flag, _) if flag == "--drafts"));
+            let err = parse(&[], &[("SITE_OFFLINE", "yes")]).unwrap_err();
+            assert!(matches!(err, ConfigError::InvalidValue(flag, _) if flag == "--offline"));
+            let err = parse(&["check", "build"], &[]).unwrap_err();
+            assert!(matches!(err, ConfigError::UnexpectedArgument(arg) if arg == "build"));
+        }
+
+        #[test]
+        fn invalid_config_files_are_rejected() {
+            let err = parse(&["--config", "does/not/exist.toml"], &[]).unwrap_err();
+            assert!(matches!(err, ConfigError::Io(..)));
+
+            let file = config_file("unknown_field = 1");
+            let err = parse(&["--config", file.path().to_str().unwrap()], &[]).unwrap_err();
+            assert!(matches!(err, ConfigError::Parse(..)));
+        }
+    }
+}
+
+#[cfg_not_ssr]
+pub use self::client::*;
+
+#[cfg_not_ssr]
+mod client {
+    use std::sync::LazyLock;
+
+    use sycamore::prelude::document;
+
+    use super::{SiteConfig, SITE_CONFIG_ID};
+
+    static SITE_CONFIG: LazyLock<SiteConfig> = LazyLock::new(|| {
+        let json = document()
+            .get_element_by_id(SITE_CONFIG_ID)
+            .and_then(|element| element.text_content())
+            .expect("site config is missing from the page");
+        serde_json::from_str(&json).expect("could not parse site config")
+    });
+
+    /// Get the configuration of the site that was embedded into the page during SSR.
+    pub fn site_config() -> &'static SiteConfig {
+        &SITE_CONFIG
+    }
+}
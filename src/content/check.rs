@@ -0,0 +1,221 @@
+//! Validate the docs tree without rendering anything.
+//!
+//! This is run with `sycamore-website check`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    doc_path,
+    error::{ContentError, ContentErrorCause, Location},
+    docs_dir, DOCS, POSTS, VERSIONS,
+};
+
+/// Load all the content and check it for problems that do not prevent the site from building but
+/// are most likely mistakes.
+pub fn check() -> Vec<ContentError> {
+    let mut errors = match super::load() {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors,
+    };
+
+    let docs = DOCS
+        .iter()
+        .map(|(page, doc)| {
+            (
+                doc_path(&page.file_path()),
+                doc.front_matter.title.as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let posts = POSTS
+        .iter()
+        .map(|(name, post)| {
+            (
+                doc_path(&format!("posts/{name}")),
+                post.front_matter.title.as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Docs that could not be loaded are missing from `DOCS`, but they have already been reported.
+    let failed = errors
+        .iter()
+        .map(|err| err.path.clone())
+        .collect::<HashSet<_>>();
+    let version_dirs = VERSIONS
+        .iter()
+        .map(|version| docs_dir().join(version.as_str()))
+        .collect::<Vec<_>>();
+    errors.extend(check_orphans(&version_dirs, &docs, &failed));
+    // Titles only need to be unique within each version of the book.
+    for version in VERSIONS.iter() {
+        let version_dir = docs_dir().join(version.as_str());
+        let version_docs = docs
+            .iter()
+            .filter(|(path, _)| path.starts_with(&version_dir))
+            .cloned()
+            .collect::<Vec<_>>();
+        errors.extend(check_duplicate_titles(&version_docs));
+    }
+    errors.extend(check_duplicate_titles(&posts));
+    errors.extend(
+        docs.iter()
+            .chain(posts.iter())
+            .filter_map(|(path, _)| check_empty_body(path)),
+    );
+
+    errors
+}
+
+/// Find all the markdown files in the `version_dirs` of the book that are not referenced from
+/// `sections.json` or from the subsections of another doc. The files in `failed` are skipped, as
+/// are the versions whose `sections.json` is in `failed`, since none of their docs were loaded.
+fn check_orphans(
+    version_dirs: &[PathBuf],
+    docs: &[(PathBuf, &str)],
+    failed: &HashSet<PathBuf>,
+) -> Vec<ContentError> {
+    let referenced = docs
+        .iter()
+        .map(|(path, _)| path)
+        .chain(failed)
+        .collect::<HashSet<_>>();
+
+    let mut files = Vec::new();
+    for version_dir in version_dirs {
+        if failed.contains(&version_dir.join("sections.json")) {
+            continue;
+        }
+        if let Err(err) = find_md_files(version_dir, &mut files) {
+            return vec![ContentError::new(version_dir, ContentErrorCause::Io(err))];
+        }
+    }
+    files.sort();
+
+    files
+        .into_iter()
+        .filter(|path| !referenced.contains(path))
+        .map(|path| ContentError::new(path, ContentErrorCause::OrphanDoc))
+        .collect()
+}
+
+fn find_md_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_md_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Find all the pages that have the same title as another page.
+fn check_duplicate_titles(pages: &[(PathBuf, &str)]) -> Vec<ContentError> {
+    let mut pages = pages.to_vec();
+    pages.sort();
+
+    let mut seen = HashMap::<&str, &Path>::new();
+    let mut errors = Vec::new();
+    for (path, title) in &pages {
+        match seen.get(title) {
+            Some(first) => {
+                let location = fs::read_to_string(path)
+                    .ok()
+                    .and_then(|md| Location::find(&md, "title"));
+                errors.push(
+                    ContentError::new(
+                        path,
+                        ContentErrorCause::DuplicateTitle {
+                            title: title.to_string(),
+                            first: first.to_path_buf(),
+                        },
+                    )
+                    .with_location(location),
+                );
+            }
+            None => {
+                seen.insert(title, path);
+            }
+        }
+    }
+    errors
+}
+
+/// Check that the page has some content after the frontmatter.
+fn check_empty_body(path: &Path) -> Option<ContentError> {
+    let md = fs::read_to_string(path).ok()?;
+    let md = md.trim();
+    let body = match md.split_once("---") {
+        Some(("", rest)) => rest.split_once("---").map_or("", |(_, body)| body),
+        _ => md,
+    };
+    body.trim()
+        .is_empty()
+        .then(|| ContentError::new(path, ContentErrorCause::EmptyBody))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a version of the book with the given files, and return its directory.
+    fn version_dir(root: &Path, version: &str, files: &[&str]) -> PathBuf {
+        let dir = root.join(version);
+        for file in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        dir
+    }
+
+    fn orphans(errors: Vec<ContentError>) -> Vec<PathBuf> {
+        errors
+            .into_iter()
+            .filter(|err| matches!(err.cause, ContentErrorCause::OrphanDoc))
+            .map(|err| err.path)
+            .collect()
+    }
+
+    #[test]
+    fn unreferenced_docs_are_orphans() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = version_dir(
+            root.path(),
+            "next",
+            &["sections.json", "intro.md", "intro/setup.md", "stray.md"],
+        );
+        let docs = [(dir.join("intro.md"), "Intro"), (dir.join("intro/setup.md"), "Setup")];
+
+        let errors = check_orphans(std::slice::from_ref(&dir), &docs, &HashSet::new());
+        assert_eq!(orphans(errors), [dir.join("stray.md")]);
+    }
+
+    #[test]
+    fn docs_that_failed_to_load_are_not_orphans() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = version_dir(root.path(), "next", &["sections.json", "intro.md", "broken.md"]);
+        let docs = [(dir.join("intro.md"), "Intro")];
+        let failed = HashSet::from([dir.join("broken.md")]);
+
+        assert!(check_orphans(&[dir], &docs, &failed).is_empty());
+    }
+
+    #[test]
+    fn versions_with_a_broken_sections_json_have_no_orphans() {
+        let root = tempfile::tempdir().unwrap();
+        let broken = version_dir(root.path(), "next", &["sections.json", "intro.md", "guide.md"]);
+        let ok = version_dir(root.path(), "v0.9", &["sections.json", "intro.md", "stray.md"]);
+        let docs = [(ok.join("intro.md"), "Intro")];
+        let failed = HashSet::from([broken.join("sections.json")]);
+
+        let errors = check_orphans(&[broken, ok.clone()], &docs, &failed);
+        assert_eq!(orphans(errors), [ok.join("stray.md")]);
+    }
+}
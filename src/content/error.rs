@@ -0,0 +1,212 @@
+//! Errors encountered while loading the content of the site.
+//!
+//! Instead of panicking on the first bad file, the loader collects every error it finds so that
+//! they can all be reported at once.
+
+use std::{
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+/// A position inside a content file. Both `line` and `column` are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+    /// The text of the line, used for displaying a snippet.
+    pub text: String,
+}
+
+impl Location {
+    /// Find the location of the byte offset `index` in `source`.
+    pub fn from_index(source: &str, index: usize) -> Self {
+        let index = index.min(source.len());
+        let before = &source[..index];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line = before.matches('\n').count() + 1;
+        let column = before[line_start..].chars().count() + 1;
+        let text = source[line_start..]
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        Self { line, column, text }
+    }
+
+    /// Find the location of the 1-based `line` and `column` in `source`.
+    pub fn from_line_column(source: &str, line: usize, column: usize) -> Self {
+        let text = source
+            .lines()
+            .nth(line.saturating_sub(1))
+            .unwrap_or_default()
+            .to_string();
+        Self { line, column, text }
+    }
+
+    /// Find the first line in `source` that contains `needle`.
+    pub fn find(source: &str, needle: &str) -> Option<Self> {
+        let index = source.find(needle)?;
+        Some(Self::from_index(source, index))
+    }
+}
+
+/// The underlying cause of a [`ContentError`].
+#[derive(Debug)]
+pub enum ContentErrorCause {
+    /// The file could not be read.
+    Io(std::io::Error),
+    /// A JSON file could not be parsed.
+    Json(serde_json::Error),
+    /// A TOML file could not be parsed.
+    Toml(Box<toml::de::Error>),
+    /// A markdown file or its frontmatter could not be parsed.
+    Parse(mdsycx::ParseError),
+    /// A subsection listed in the frontmatter could not be read.
+    MissingSubsection {
+        subsection: String,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A subsection listed subsections of its own.
+    NestedSubsections,
+    /// No versions of the book were found.
+    NoVersions,
+    /// A doc is not referenced from `sections.json` or from any subsections.
+    OrphanDoc,
+    /// Another page already has the same title.
+    DuplicateTitle { title: String, first: PathBuf },
+    /// The page has no content besides the frontmatter.
+    EmptyBody,
+    /// A post lists an author that is not in the authors file.
+    UnknownAuthor(String),
+    /// A tag has no letters or digits, so it has no slug to use in its URL.
+    EmptyTagSlug(String),
+    /// A tag has the same slug as a differently written tag, e.g. `C++` and `C`.
+    TagCollision { other: String, first: PathBuf },
+}
+
+impl Display for ContentErrorCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read file: {err}"),
+            Self::Json(err) => write!(f, "could not parse json: {}", strip_location(err)),
+            Self::Toml(err) => write!(f, "could not parse toml: {}", err.message()),
+            Self::Parse(mdsycx::ParseError::DeserializeError(err)) => {
+                write!(f, "could not parse frontmatter: {}", strip_location(err))
+            }
+            Self::Parse(err) => write!(f, "could not parse markdown: {err}"),
+            Self::MissingSubsection {
+                subsection,
+                path,
+                source,
+            } => write!(
+                f,
+                "subsection `{subsection}` could not be read from `{}`: {source}",
+                path.display()
+            ),
+            Self::NestedSubsections => write!(f, "subsections cannot have subsections"),
+            Self::NoVersions => write!(
+                f,
+                "could not find any versions of the book (directories with a `sections.json`)"
+            ),
+            Self::OrphanDoc => write!(
+                f,
+                "doc is not referenced from `sections.json` or from any subsections"
+            ),
+            Self::DuplicateTitle { title, first } => write!(
+                f,
+                "title `{title}` is already used by `{}`",
+                first.display()
+            ),
+            Self::EmptyBody => write!(f, "page has an empty body"),
+            Self::UnknownAuthor(author) => write!(
+                f,
+                "author `{author}` is not listed in `authors.toml` or `authors.json`"
+            ),
+            Self::EmptyTagSlug(tag) => write!(
+                f,
+                "tag `{tag}` needs at least one letter or digit to be used in a URL"
+            ),
+            Self::TagCollision { other, first } => write!(
+                f,
+                "tag has the same URL as tag `{other}` in `{}`",
+                first.display()
+            ),
+        }
+    }
+}
+
+/// Remove the ` at line X column Y` suffix that serde errors add, since we render the location
+/// ourselves.
+fn strip_location(err: &impl Display) -> String {
+    let msg = err.to_string();
+    match msg.rsplit_once(" at line ") {
+        Some((msg, _)) => msg.to_string(),
+        None => msg,
+    }
+}
+
+/// An error in a content file.
+#[derive(Debug)]
+pub struct ContentError {
+    /// The file in which the error occurred.
+    pub path: PathBuf,
+    /// The location of the error inside the file, if known.
+    pub location: Option<Location>,
+    pub cause: ContentErrorCause,
+}
+
+impl ContentError {
+    pub fn new(path: impl AsRef<Path>, cause: ContentErrorCause) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            location: None,
+            cause,
+        }
+    }
+
+    pub fn with_location(mut self, location: Option<Location>) -> Self {
+        self.location = location;
+        self
+    }
+}
+
+impl std::error::Error for ContentError {}
+
+/// Renders the error in the same style as rustc diagnostics.
+impl Display for ContentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "error: {}", self.cause)?;
+        match &self.location {
+            Some(location) => {
+                let gutter = " ".repeat(location.line.to_string().len());
+                writeln!(
+                    f,
+                    "{gutter}--> {}:{}:{}",
+                    self.path.display(),
+                    location.line,
+                    location.column
+                )?;
+                writeln!(f, "{gutter} |")?;
+                writeln!(f, "{} | {}", location.line, location.text)?;
+                write!(
+                    f,
+                    "{gutter} | {}^",
+                    " ".repeat(location.column.saturating_sub(1))
+                )
+            }
+            None => write!(f, " --> {}", self.path.display()),
+        }
+    }
+}
+
+/// Print all the errors to stderr, followed by a summary line.
+pub fn report(errors: &[ContentError]) {
+    for err in errors {
+        eprintln!("{err}\n");
+    }
+    match errors.len() {
+        1 => eprintln!("error: aborting due to 1 previous error"),
+        n => eprintln!("error: aborting due to {n} previous errors"),
+    }
+}
@@ -0,0 +1,181 @@
+//! Generate RSS and Atom feeds for the blog posts.
+
+use std::fmt::Write;
+
+use mdsycx::ParseRes;
+use sycamore::prelude::*;
+
+use super::{post_authors, sorted_posts, Date, PostFrontmatter, POSTS, TAGS};
+use crate::config::site_config;
+
+static FEED_TITLE: &str = "Sycamore Blog";
+static FEED_DESCRIPTION: &str = "News and updates about Sycamore";
+
+/// A feed of blog posts, written to `feed.xml` (RSS) and `atom.xml` (Atom).
+pub struct Feed {
+    pub title: String,
+    pub description: String,
+    /// The path of the page that lists the posts of the feed.
+    pub page: String,
+    /// The directory that the feed files are written to, e.g. `/` or `/blog/tag/release/`.
+    pub dir: String,
+    /// The posts in the feed, sorted from newest to oldest.
+    pub posts: Vec<(&'static String, &'static ParseRes<PostFrontmatter>)>,
+}
+
+/// The directory that the feeds of the tag with the given slug are written to.
+pub fn tag_feed_dir(tag: &str) -> String {
+    format!("/blog/tag/{tag}/")
+}
+
+/// Get the feed of all the posts, followed by a feed for each tag.
+pub fn feeds() -> Vec<Feed> {
+    let all = Feed {
+        title: FEED_TITLE.to_string(),
+        description: FEED_DESCRIPTION.to_string(),
+        page: "/".to_string(),
+        dir: "/".to_string(),
+        posts: sorted_posts(),
+    };
+    let tags = TAGS.iter().map(|(slug, tag)| Feed {
+        title: format!("{FEED_TITLE}: {}", tag.name),
+        description: format!("Posts about {} on the Sycamore blog", tag.name),
+        page: format!("/blog/tag/{slug}"),
+        dir: tag_feed_dir(slug),
+        posts: tag
+            .posts
+            .iter()
+            .filter_map(|id| POSTS.get_key_value(id))
+            .collect(),
+    });
+    std::iter::once(all).chain(tags).collect()
+}
+
+/// Escape a string so that it can be included in XML text or attribute values.
+pub fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Render the body of the post to HTML. Links are made absolute since feed readers do not know
+/// where the post came from.
+fn render_post_html(post: &ParseRes<PostFrontmatter>) -> String {
+    let base_url = &site_config().base_url;
+    let body = post.body.clone();
+    let html = sycamore::render_to_string(|| {
+        view! {
+            sycamore::web::NoHydrate {
+                mdsycx::MDSycX(body=body)
+            }
+        }
+    });
+    html.replace("href=\"/", &format!("href=\"{base_url}/"))
+        .replace("src=\"/", &format!("src=\"{base_url}/"))
+}
+
+/// Generate an RSS 2.0 feed of the posts in `feed`.
+pub fn generate_rss_xml(feed: &Feed) -> Result<String, std::fmt::Error> {
+    let base_url = &site_config().base_url;
+    let mut buf = String::new();
+
+    write!(
+        &mut buf,
+        r#"<?xml version="1.0" encoding="UTF-8"?><rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/elements/1.1/"><channel>"#
+    )?;
+    write!(
+        &mut buf,
+        r#"<title>{title}</title><link>{base_url}{page}</link><description>{description}</description><language>en</language><atom:link href="{base_url}{dir}feed.xml" rel="self" type="application/rss+xml"/>"#,
+        title = escape_xml(&feed.title),
+        page = feed.page,
+        description = escape_xml(&feed.description),
+        dir = feed.dir,
+    )?;
+
+    if let Some((_, latest)) = feed.posts.first() {
+        let date = latest.front_matter.date.to_rfc2822();
+        write!(&mut buf, r#"<lastBuildDate>{date}</lastBuildDate>"#)?;
+    }
+
+    for (id, post) in &feed.posts {
+        let link = format!("{base_url}/post/{id}");
+        write!(
+            &mut buf,
+            r#"<item><title>{title}</title><link>{link}</link><guid isPermaLink="true">{link}</guid><description>{content}</description><pubDate>{date}</pubDate>"#,
+            title = escape_xml(&post.front_matter.title),
+            content = escape_xml(&render_post_html(post)),
+            date = post.front_matter.date.to_rfc2822(),
+        )?;
+        // RSS requires an email address in `<author>`, so use the Dublin Core creator instead.
+        for author in post_authors(&post.front_matter) {
+            write!(&mut buf, r#"<dc:creator>{}</dc:creator>"#, escape_xml(&author.name))?;
+        }
+        for tag in &post.front_matter.tags {
+            write!(&mut buf, r#"<category>{}</category>"#, escape_xml(tag))?;
+        }
+        write!(&mut buf, r#"</item>"#)?;
+    }
+
+    write!(&mut buf, r#"</channel></rss>"#)?;
+
+    Ok(buf)
+}
+
+/// Generate an Atom feed of the posts in `feed`.
+pub fn generate_atom_xml(feed: &Feed) -> Result<String, std::fmt::Error> {
+    let base_url = &site_config().base_url;
+    let mut buf = String::new();
+
+    write!(
+        &mut buf,
+        r#"<?xml version="1.0" encoding="UTF-8"?><feed xmlns="http://www.w3.org/2005/Atom">"#
+    )?;
+    write!(
+        &mut buf,
+        r#"<title>{title}</title><subtitle>{description}</subtitle><id>{base_url}{page}</id><link href="{base_url}{page}"/><link href="{base_url}{dir}atom.xml" rel="self"/><author><name>Sycamore</name></author>"#,
+        title = escape_xml(&feed.title),
+        description = escape_xml(&feed.description),
+        page = feed.page,
+        dir = feed.dir,
+    )?;
+
+    // `<updated>` is required, so fall back to the date of the build if there are no posts yet.
+    let updated = feed
+        .posts
+        .first()
+        .map_or_else(Date::today, |(_, post)| post.front_matter.date);
+    write!(&mut buf, r#"<updated>{}</updated>"#, updated.to_rfc3339())?;
+
+    for (id, post) in &feed.posts {
+        let link = format!("{base_url}/post/{id}");
+        let date = post.front_matter.date.to_rfc3339();
+        write!(
+            &mut buf,
+            r#"<entry><title>{title}</title><id>{link}</id><link href="{link}"/><published>{date}</published><updated>{date}</updated><summary>{summary}</summary>"#,
+            title = escape_xml(&post.front_matter.title),
+            summary = escape_xml(&post.front_matter.description),
+        )?;
+        for author in post_authors(&post.front_matter) {
+            write!(
+                &mut buf,
+                r#"<author><name>{name}</name><uri>{uri}</uri></author>"#,
+                name = escape_xml(&author.name),
+                uri = escape_xml(&author.github_url()),
+            )?;
+        }
+        for tag in &post.front_matter.tags {
+            write!(&mut buf, r#"<category term="{}"/>"#, escape_xml(tag))?;
+        }
+        write!(
+            &mut buf,
+            r#"<content type="html">{content}</content></entry>"#,
+            content = escape_xml(&render_post_html(post)),
+        )?;
+    }
+
+    write!(&mut buf, r#"</feed>"#)?;
+
+    Ok(buf)
+}
@@ -0,0 +1,756 @@
+//! Syntax highlighting for code blocks at build time.
+//!
+//! Code is split into `<span>`s with the same class names as Prism so that the gruvbox theme keeps
+//! working, e.g. `<span class="token keyword">fn</span>`. Only the languages that are used in the
+//! docs are supported: Rust, TOML, shell, HTML and JavaScript. Code in other languages is left
+//! as plain text.
+
+use mdsycx::{BodyRes, Event};
+
+use super::{body_events, body_from_events};
+
+/// Highlight all the fenced code blocks in the body, i.e. `<pre><code class="language-*">`.
+pub fn highlight_code_blocks(body: &BodyRes) -> BodyRes {
+    let events = body_events(body);
+    let mut out = Vec::with_capacity(events.len());
+    let mut i = 0;
+    while i < events.len() {
+        if let [Event::Start(pre), Event::Start(code), ..] = &events[i..] {
+            if pre == "pre" && code == "code" {
+                if let Some((block, len)) = highlight_block(&events[i + 1..]) {
+                    out.push(Event::Start("pre".to_string()));
+                    out.extend(block);
+                    i += 1 + len;
+                    continue;
+                }
+            }
+        }
+        out.push(events[i].clone());
+        i += 1;
+    }
+    body_from_events(out)
+}
+
+/// Highlight a `<code>` element that only contains text. Returns the new events for the element
+/// (including an attribute for the parent `<pre>`) and the number of events that were replaced.
+fn highlight_block(events: &[Event]) -> Option<(Vec<Event>, usize)> {
+    let mut attrs = Vec::new();
+    let mut code = String::new();
+    let mut len = 1;
+    loop {
+        match events.get(len)? {
+            Event::Attr(key, value) => attrs.push((key.clone(), value.clone())),
+            Event::Text(text) => code.push_str(text),
+            Event::End => break,
+            Event::Start(_) => return None,
+        }
+        len += 1;
+    }
+    let lang = attrs
+        .iter()
+        .filter(|(key, _)| key == "class")
+        .flat_map(|(_, value)| value.split_whitespace())
+        .find_map(|class| class.strip_prefix("language-"))?;
+
+    // The theme styles `pre` elements with a language class.
+    let mut block = vec![
+        Event::Attr("class".to_string(), format!("language-{lang}")),
+        Event::Start("code".to_string()),
+    ];
+    block.extend(attrs.iter().cloned().map(|(key, value)| Event::Attr(key, value)));
+    block.extend(highlight_events(&code, lang));
+    block.push(Event::End);
+    Some((block, len + 1))
+}
+
+/// Highlight a snippet of code in the given language.
+pub fn highlight(code: &str, lang: &str) -> BodyRes {
+    body_from_events(highlight_events(code, lang))
+}
+
+fn highlight_events(code: &str, lang: &str) -> Vec<Event> {
+    let mut out = Tokens::default();
+    match lang {
+        "rust" | "rs" => clike(code, &mut out, Clike::Rust),
+        "js" | "javascript" | "mjs" => clike(code, &mut out, Clike::JavaScript),
+        "toml" => toml(code, &mut out),
+        "bash" | "sh" | "shell" | "console" | "zsh" => shell(code, &mut out),
+        "html" | "xml" | "svg" | "markup" => markup(code, &mut out),
+        _ => out.plain(code),
+    }
+    out.events
+}
+
+/// Collects the highlighted code as markdown events.
+#[derive(Default)]
+struct Tokens {
+    events: Vec<Event>,
+}
+
+impl Tokens {
+    fn plain(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        match self.events.last_mut() {
+            Some(Event::Text(last)) => last.push_str(text),
+            _ => self.events.push(Event::Text(text.to_string())),
+        }
+    }
+
+    fn token(&mut self, class: &str, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.open(class);
+        self.plain(text);
+        self.close();
+    }
+
+    /// Start a token that contains other tokens. Must be followed by a call to [`Tokens::close`].
+    fn open(&mut self, class: &str) {
+        self.events.push(Event::Start("span".to_string()));
+        self.events
+            .push(Event::Attr("class".to_string(), format!("token {class}")));
+    }
+
+    fn close(&mut self) {
+        self.events.push(Event::End);
+    }
+}
+
+/// A cursor over the code that is being highlighted.
+struct Scanner<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// The character before the cursor.
+    fn prev(&self) -> Option<char> {
+        self.src[..self.pos].chars().next_back()
+    }
+
+    /// Advance by `len` bytes and return the text that was skipped over.
+    fn take(&mut self, len: usize) -> &'a str {
+        let text = &self.rest()[..len];
+        self.pos += len;
+        text
+    }
+
+    /// Advance by one character.
+    fn take_char(&mut self) -> &'a str {
+        self.take(self.peek().map_or(0, char::len_utf8))
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let len = self.rest().find(|c| !f(c)).unwrap_or(self.rest().len());
+        self.take(len)
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn ident_len(s: &str) -> usize {
+    if !s.starts_with(is_ident_start) {
+        return 0;
+    }
+    s.find(|c: char| !c.is_alphanumeric() && c != '_')
+        .unwrap_or(s.len())
+}
+
+/// The length of the current line, without the newline.
+fn line_len(s: &str) -> usize {
+    s.find('\n').unwrap_or(s.len())
+}
+
+/// The length of a string that starts with `quote`, including both quotes. Backslash escapes are
+/// skipped over.
+fn quoted_len(s: &str, quote: char) -> usize {
+    let mut chars = s.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == quote {
+            return i + c.len_utf8();
+        }
+    }
+    s.len()
+}
+
+/// The length of a number literal. Suffixes such as `u32` and exponents are included.
+fn number_len(s: &str) -> usize {
+    let mut len = 0;
+    let bytes = s.as_bytes();
+    while let Some(&b) = bytes.get(len) {
+        let is_decimal_point = b == b'.' && bytes.get(len + 1).is_some_and(u8::is_ascii_digit);
+        if !b.is_ascii_alphanumeric() && b != b'_' && !is_decimal_point {
+            break;
+        }
+        len += 1;
+    }
+    len
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Clike {
+    Rust,
+    JavaScript,
+}
+
+static RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "type", "unsafe", "use",
+    "where", "while", "yield",
+];
+
+static JS_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "case", "catch", "class", "const", "continue", "debugger",
+    "default", "delete", "do", "else", "export", "extends", "finally", "for", "from", "function",
+    "if", "import", "in", "instanceof", "let", "new", "null", "of", "return", "static", "super",
+    "switch", "this", "throw", "try", "typeof", "undefined", "var", "void", "while", "with",
+    "yield",
+];
+
+/// Highlight Rust or JavaScript, which share most of their lexical structure.
+fn clike(code: &str, out: &mut Tokens, lang: Clike) {
+    let keywords = match lang {
+        Clike::Rust => RUST_KEYWORDS,
+        Clike::JavaScript => JS_KEYWORDS,
+    };
+    let rust = lang == Clike::Rust;
+
+    let mut s = Scanner::new(code);
+    while let Some(c) = s.peek() {
+        let rest = s.rest();
+        if rest.starts_with("//") {
+            out.token("comment", s.take(line_len(rest)));
+        } else if rest.starts_with("/*") {
+            out.token("comment", s.take(block_comment_len(rest, rust)));
+        } else if c == '"' || (!rust && c == '\'') {
+            out.token("string", s.take(quoted_len(rest, c)));
+        } else if !rust && c == '`' {
+            out.token("template-string string", s.take(quoted_len(rest, c)));
+        } else if let Some(len) = rust.then(|| rust_string_len(rest)).flatten() {
+            out.token("string", s.take(len));
+        } else if rust && c == '\'' {
+            match rust_char_len(rest) {
+                Some(len) => out.token("char", s.take(len)),
+                None => {
+                    let len = 1 + ident_len(&rest[1..]);
+                    out.token("lifetime-annotation symbol", s.take(len));
+                }
+            }
+        } else if rust && (rest.starts_with("#[") || rest.starts_with("#![")) {
+            out.token("attribute attr-name", s.take(attribute_len(rest)));
+        } else if c.is_ascii_digit() {
+            out.token("number", s.take(number_len(rest)));
+        } else if is_ident_start(c) || (!rust && c == '$') {
+            let len = if c == '$' {
+                1 + rest[1..]
+                    .find(|c: char| !c.is_alphanumeric() && c != '_' && c != '$')
+                    .unwrap_or(rest.len() - 1)
+            } else {
+                ident_len(rest)
+            };
+            let ident = &rest[..len];
+            let after = &rest[len..];
+            if rust && after.starts_with('!') && !after.starts_with("!=") {
+                out.token("macro property", s.take(len + 1));
+                continue;
+            }
+            let class = if ident == "true" || ident == "false" {
+                Some("boolean")
+            } else if keywords.contains(&ident) {
+                Some("keyword")
+            } else if after.starts_with('(') || after.starts_with("::<") {
+                Some("function")
+            } else if ident.starts_with(char::is_uppercase) {
+                Some("class-name")
+            } else {
+                None
+            };
+            let ident = s.take(len);
+            match class {
+                Some(class) => out.token(class, ident),
+                None => out.plain(ident),
+            }
+        } else if "+-*/%^!&|=<>?~".contains(c) {
+            out.token("operator", s.take_while(|c| "+-*/%^!&|=<>?~".contains(c)));
+        } else if "{}[]();,.:#@".contains(c) {
+            out.token("punctuation", s.take_char());
+        } else {
+            out.plain(s.take_char());
+        }
+    }
+}
+
+/// The length of a block comment, including `/*` and `*/`. Block comments can be nested in Rust
+/// but not in JavaScript.
+fn block_comment_len(s: &str, nested: bool) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < s.len() {
+        if s[i..].starts_with("/*") && (nested || depth == 0) {
+            depth += 1;
+            i += 2;
+        } else if s[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += s[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    s.len()
+}
+
+/// The length of a Rust string literal that starts with a prefix, e.g. `b"..."` or `r#"..."#`.
+fn rust_string_len(s: &str) -> Option<usize> {
+    let prefix = ["br", "r", "b", "c"]
+        .into_iter()
+        .find(|prefix| s.starts_with(prefix))?;
+    let after = &s[prefix.len()..];
+    if prefix.ends_with('r') {
+        let hashes = after.len() - after.trim_start_matches('#').len();
+        let body = after[hashes..].strip_prefix('"')?;
+        let end = "\"".to_string() + &"#".repeat(hashes);
+        let close = body.find(&end).map_or(body.len(), |i| i + end.len());
+        Some(prefix.len() + hashes + 1 + close)
+    } else {
+        after
+            .starts_with('"')
+            .then(|| prefix.len() + quoted_len(after, '"'))
+    }
+}
+
+/// The length of a char literal starting at `'`, or `None` if it is a lifetime instead.
+fn rust_char_len(s: &str) -> Option<usize> {
+    let mut chars = s.char_indices().skip(1);
+    match chars.next()? {
+        // Skip over the escaped character so that `'\''` works.
+        (_, '\\') => s.get(3..)?.find('\'').map(|i| i + 4),
+        (_, c) => {
+            let (i, next) = chars.next()?;
+            (next == '\'').then_some(i + 1).filter(|_| c != '\'')
+        }
+    }
+}
+
+/// The length of an attribute such as `#[derive(Debug)]`, up to the matching `]`.
+fn attribute_len(s: &str) -> usize {
+    let mut depth = 0;
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            '"' => {
+                let len = quoted_len(&s[i..], '"');
+                while chars.next().is_some_and(|(j, _)| j < i + len - 1) {}
+            }
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+/// Highlight a TOML file such as `Cargo.toml`.
+fn toml(code: &str, out: &mut Tokens) {
+    let mut s = Scanner::new(code);
+    let mut line_start = true;
+    while let Some(c) = s.peek() {
+        let rest = s.rest();
+        if c == '\n' {
+            line_start = true;
+            out.plain(s.take_char());
+            continue;
+        }
+        if c.is_whitespace() {
+            out.plain(s.take_char());
+            continue;
+        }
+        let at_line_start = std::mem::replace(&mut line_start, false);
+
+        if c == '#' {
+            out.token("comment", s.take(line_len(rest)));
+        } else if c == '[' && at_line_start {
+            // A table header such as `[dependencies]` or `[[bin]]`.
+            let open = if rest.starts_with("[[") { 2 } else { 1 };
+            out.token("punctuation", s.take(open));
+            out.token("table class-name", s.take_while(|c| c != ']' && c != '\n'));
+            out.token("punctuation", s.take_while(|c| c == ']'));
+        } else if let Some(len) = toml_key_len(rest) {
+            out.token("key property", s.take(len));
+        } else if rest.starts_with("\"\"\"") || rest.starts_with("'''") {
+            let close = rest[3..].find(&rest[..3]).map_or(rest.len(), |i| i + 6);
+            out.token("string", s.take(close));
+        } else if c == '"' {
+            out.token("string", s.take(quoted_len(rest, '"')));
+        } else if c == '\'' {
+            let close = rest[1..].find('\'').map_or(rest.len(), |i| i + 2);
+            out.token("string", s.take(close));
+        } else if c.is_ascii_digit() || ((c == '+' || c == '-') && rest[1..].starts_with(|c: char| c.is_ascii_digit())) {
+            // Numbers and dates.
+            let len = 1 + rest[1..]
+                .find(|c: char| !c.is_ascii_alphanumeric() && !"-+:._".contains(c))
+                .unwrap_or(rest.len() - 1);
+            out.token("number", s.take(len));
+        } else if rest.starts_with("true") || rest.starts_with("false") {
+            let len = ident_len(rest);
+            let word = s.take(len);
+            match word {
+                "true" | "false" => out.token("boolean", word),
+                _ => out.plain(word),
+            }
+        } else if ".,=[]{}".contains(c) {
+            out.token("punctuation", s.take_char());
+        } else {
+            out.plain(s.take_char());
+        }
+    }
+}
+
+/// The length of a (possibly dotted or quoted) TOML key, if the text starts with one followed by
+/// an `=`.
+fn toml_key_len(s: &str) -> Option<usize> {
+    let mut len = 0;
+    loop {
+        let part = &s[len..];
+        let part_len = match part.chars().next()? {
+            '"' => quoted_len(part, '"'),
+            '\'' => part[1..].find('\'')? + 2,
+            _ => part
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '-')
+                .unwrap_or(part.len()),
+        };
+        if part_len == 0 {
+            return None;
+        }
+        len += part_len;
+        if s[len..].starts_with('.') {
+            len += 1;
+        } else {
+            break;
+        }
+    }
+    let after = s[len..].trim_start_matches([' ', '\t']);
+    (after.starts_with('=') && !after.starts_with("==")).then_some(len)
+}
+
+static SHELL_KEYWORDS: &[&str] = &[
+    "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac",
+    "in", "function", "return", "export", "local", "select",
+];
+
+/// Highlight shell commands. The first word of each command is highlighted as a function.
+fn shell(code: &str, out: &mut Tokens) {
+    let mut s = Scanner::new(code);
+    let mut command_start = true;
+    while let Some(c) = s.peek() {
+        let rest = s.rest();
+        if c == '\n' {
+            command_start = true;
+            out.plain(s.take_char());
+        } else if c.is_whitespace() || c == '\\' {
+            out.plain(s.take_char());
+        } else if c == '#' && s.prev().is_none_or(char::is_whitespace) {
+            out.token("comment", s.take(line_len(rest)));
+        } else if c == '"' {
+            out.token("string", s.take(quoted_len(rest, '"')));
+        } else if c == '\'' {
+            let close = rest[1..].find('\'').map_or(rest.len(), |i| i + 2);
+            out.token("string", s.take(close));
+        } else if c == '$' {
+            let len = if rest.starts_with("${") {
+                rest.find('}').map_or(rest.len(), |i| i + 1)
+            } else if rest[1..].starts_with(|c: char| "@*#?$!0123456789".contains(c)) {
+                2
+            } else {
+                1 + ident_len(&rest[1..])
+            };
+            if len == 1 {
+                // A prompt such as `$ cargo build`.
+                out.plain(s.take(1));
+            } else {
+                out.token("variable", s.take(len));
+            }
+        } else if "|&;<>".contains(c) {
+            let op = s.take_while(|c| "|&;<>".contains(c));
+            command_start = !op.starts_with(['<', '>']);
+            out.token("operator", op);
+        } else if "(){}[]`".contains(c) {
+            out.token("punctuation", s.take_char());
+        } else {
+            // A word can continue after a variable or a string, e.g. `$HOME/bin`.
+            let continued = s.prev().is_some_and(|c| !c.is_whitespace() && !"|&;(".contains(c));
+            let word = s.take_while(|c| !c.is_whitespace() && !"\"'$|&;<>(){}[]`".contains(c));
+            if SHELL_KEYWORDS.contains(&word) {
+                out.token("keyword", word);
+                command_start = true;
+            } else if command_start && !continued && !word.contains('=') {
+                out.token("function", word);
+                command_start = false;
+            } else if word.chars().all(|c| c.is_ascii_digit()) {
+                out.token("number", word);
+            } else {
+                out.plain(word);
+            }
+        }
+    }
+}
+
+/// Highlight HTML or XML. The contents of `<script>` elements are highlighted as JavaScript.
+fn markup(code: &str, out: &mut Tokens) {
+    let mut s = Scanner::new(code);
+    while let Some(c) = s.peek() {
+        let rest = s.rest();
+        if rest.starts_with("<!--") {
+            let len = rest.find("-->").map_or(rest.len(), |i| i + 3);
+            out.token("comment", s.take(len));
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            let len = rest.find('>').map_or(rest.len(), |i| i + 1);
+            out.token("doctype", s.take(len));
+        } else if c == '<' && rest[1..].starts_with(|c: char| c.is_alphabetic() || c == '/') {
+            let closing = rest.starts_with("</");
+            let name = markup_tag(&mut s, out);
+            if !closing && name.eq_ignore_ascii_case("script") {
+                let script = s.take(s.rest().find("</").unwrap_or(s.rest().len()));
+                clike(script, out, Clike::JavaScript);
+            }
+        } else if c == '&' {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '#' && c != '&')
+                .filter(|&i| rest[i..].starts_with(';'))
+                .map_or(1, |i| i + 1);
+            out.token("entity", s.take(len));
+        } else {
+            out.plain(s.take_while(|c| c != '<' && c != '&'));
+        }
+    }
+}
+
+/// Highlight a start or end tag along with its attributes. Returns the name of the tag.
+fn markup_tag<'a>(s: &mut Scanner<'a>, out: &mut Tokens) -> &'a str {
+    out.open("tag");
+    let open = if s.rest().starts_with("</") { 2 } else { 1 };
+    out.token("punctuation", s.take(open));
+    let name = s.take_while(|c| c.is_alphanumeric() || c == '-' || c == ':');
+    out.plain(name);
+    while let Some(c) = s.peek() {
+        let rest = s.rest();
+        if c == '>' || rest.starts_with("/>") {
+            out.token("punctuation", s.take(if c == '>' { 1 } else { 2 }));
+            break;
+        } else if c.is_whitespace() || c == '/' {
+            out.plain(s.take_char());
+        } else {
+            out.token(
+                "attr-name",
+                s.take_while(|c| !c.is_whitespace() && !"=>/".contains(c)),
+            );
+            if s.peek() == Some('=') {
+                out.open("attr-value");
+                out.token("punctuation", s.take(1));
+                match s.peek() {
+                    Some(quote @ ('"' | '\'')) => {
+                        out.token("punctuation", s.take(1));
+                        out.plain(s.take_while(|c| c != quote));
+                        out.token("punctuation", s.take(s.rest().len().min(1)));
+                    }
+                    _ => out.plain(s.take_while(|c| !c.is_whitespace() && c != '>')),
+                }
+                out.close();
+            }
+        }
+    }
+    out.close();
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The highlighted code as a list of the innermost class of each piece of text (without the
+    /// `token` prefix, or an empty string for plain text) along with the text.
+    fn tokens(code: &str, lang: &str) -> Vec<(String, String)> {
+        let mut classes = Vec::new();
+        let mut tokens = Vec::new();
+        for event in highlight_events(code, lang) {
+            match event {
+                Event::Start(_) => classes.push(String::new()),
+                Event::Attr(key, value) if key == "class" => {
+                    *classes.last_mut().unwrap() = value.trim_start_matches("token ").to_string();
+                }
+                Event::Attr(..) => {}
+                Event::Text(text) => {
+                    let class = classes.last().cloned().unwrap_or_default();
+                    tokens.push((class, text));
+                }
+                Event::End => {
+                    classes.pop();
+                }
+            }
+        }
+        tokens
+    }
+
+    /// The text of the tokens with the given class.
+    fn texts(code: &str, lang: &str, class: &str) -> Vec<String> {
+        tokens(code, lang)
+            .into_iter()
+            .filter(|(c, _)| c == class)
+            .map(|(_, text)| text)
+            .collect()
+    }
+
+    #[test]
+    fn highlighting_keeps_the_code() {
+        let code = "fn main() {\n    println!(\"{}\", 'a');\n}\n";
+        for lang in ["rust", "js", "toml", "bash", "html", "unknown"] {
+            let text = tokens(code, lang)
+                .into_iter()
+                .map(|(_, text)| text)
+                .collect::<String>();
+            assert_eq!(text, code, "{lang}");
+        }
+    }
+
+    #[test]
+    fn rust_keywords_functions_and_macros() {
+        let code = "pub fn main() { let x = Vec::new(); println!(\"{x:?}\"); }";
+        assert_eq!(texts(code, "rust", "keyword"), ["pub", "fn", "let"]);
+        assert_eq!(texts(code, "rust", "function"), ["main", "new"]);
+        assert_eq!(texts(code, "rust", "class-name"), ["Vec"]);
+        assert_eq!(texts(code, "rust", "macro property"), ["println!"]);
+        assert_eq!(texts(code, "rust", "string"), ["\"{x:?}\""]);
+    }
+
+    #[test]
+    fn rust_raw_and_byte_strings() {
+        let code = r##"let s = r#"a "quoted" b"#; let b = b"\"x"; let r = br"y"; return;"##;
+        assert_eq!(
+            texts(code, "rust", "string"),
+            [r##"r#"a "quoted" b"#"##, r#"b"\"x""#, r#"br"y""#]
+        );
+        assert_eq!(texts(code, "rust", "keyword"), ["let", "let", "let", "return"]);
+    }
+
+    #[test]
+    fn rust_lifetimes_and_chars() {
+        let code = r"fn f<'a>(x: &'a char) -> char { if *x == 'b' { '\'' } else { '\n' } }";
+        assert_eq!(
+            texts(code, "rust", "lifetime-annotation symbol"),
+            ["'a", "'a"]
+        );
+        assert_eq!(texts(code, "rust", "char"), ["'b'", r"'\''", r"'\n'"]);
+        assert_eq!(
+            texts("&'static str", "rust", "lifetime-annotation symbol"),
+            ["'static"]
+        );
+        assert_eq!(texts("'🦀'", "rust", "char"), ["'🦀'"]);
+    }
+
+    #[test]
+    fn rust_nested_block_comments() {
+        let code = "/* a /* b */ c */ fn";
+        assert_eq!(texts(code, "rust", "comment"), ["/* a /* b */ c */"]);
+        assert_eq!(texts(code, "rust", "keyword"), ["fn"]);
+        // JavaScript comments do not nest.
+        assert_eq!(texts("/* a /* b */ c", "js", "comment"), ["/* a /* b */"]);
+    }
+
+    #[test]
+    fn rust_attributes() {
+        let code = "#[derive(Debug)]\n#![cfg(feature = \"]\")]\nstruct S;";
+        assert_eq!(
+            texts(code, "rust", "attribute attr-name"),
+            ["#[derive(Debug)]", "#![cfg(feature = \"]\")]"]
+        );
+    }
+
+    #[test]
+    fn unterminated_strings_and_comments_run_to_the_end() {
+        assert_eq!(texts("let s = \"abc", "rust", "string"), ["\"abc"]);
+        assert_eq!(texts("/* abc", "rust", "comment"), ["/* abc"]);
+        assert_eq!(texts("r#\"abc\"", "rust", "string"), ["r#\"abc\""]);
+        assert_eq!(texts("const s = 'abc", "js", "string"), ["'abc"]);
+        assert_eq!(texts("name = \"abc", "toml", "string"), ["\"abc"]);
+        assert_eq!(texts("echo 'abc", "bash", "string"), ["'abc"]);
+    }
+
+    #[test]
+    fn js_template_literals() {
+        let code = "const s = `a ${b} \\` c`; $el.foo('x');";
+        assert_eq!(
+            texts(code, "js", "template-string string"),
+            ["`a ${b} \\` c`"]
+        );
+        assert_eq!(texts(code, "js", "string"), ["'x'"]);
+        assert_eq!(texts(code, "js", "keyword"), ["const"]);
+        assert_eq!(texts(code, "js", "function"), ["foo"]);
+    }
+
+    #[test]
+    fn toml_tables_keys_and_values() {
+        let code = "[dependencies]\nsycamore = { version = \"0.9\", features = [\"ssr\"] }\na.b = true # c\n[[bin]]\n";
+        assert_eq!(
+            texts(code, "toml", "table class-name"),
+            ["dependencies", "bin"]
+        );
+        assert_eq!(
+            texts(code, "toml", "key property"),
+            ["sycamore", "version", "features", "a.b"]
+        );
+        assert_eq!(texts(code, "toml", "string"), ["\"0.9\"", "\"ssr\""]);
+        assert_eq!(texts(code, "toml", "boolean"), ["true"]);
+        assert_eq!(texts(code, "toml", "comment"), ["# c"]);
+    }
+
+    #[test]
+    fn shell_commands() {
+        let code = "$ cargo add sycamore # add it\nexport PATH=$HOME/bin:${PATH} && trunk serve";
+        assert_eq!(texts(code, "bash", "function"), ["cargo", "trunk"]);
+        assert_eq!(texts(code, "bash", "keyword"), ["export"]);
+        assert_eq!(texts(code, "bash", "variable"), ["$HOME", "${PATH}"]);
+        assert_eq!(texts(code, "bash", "comment"), ["# add it"]);
+        assert_eq!(texts(code, "bash", "operator"), ["&&"]);
+    }
+
+    #[test]
+    fn html_tags_attributes_and_scripts() {
+        let code = "<!-- c --><div class=\"a\">&amp;<script>let x = 1;</script></div>";
+        assert_eq!(texts(code, "html", "comment"), ["<!-- c -->"]);
+        assert_eq!(texts(code, "html", "attr-name"), ["class"]);
+        assert_eq!(texts(code, "html", "attr-value"), ["a"]);
+        assert_eq!(texts(code, "html", "entity"), ["&amp;"]);
+        let tags = texts(code, "html", "tag");
+        let tags = tags.iter().map(|tag| tag.trim()).collect::<Vec<_>>();
+        assert_eq!(tags, ["div", "script", "script", "div"]);
+        assert_eq!(texts(code, "html", "keyword"), ["let"]);
+        assert_eq!(texts(code, "html", "number"), ["1"]);
+    }
+}
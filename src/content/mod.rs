@@ -0,0 +1,1195 @@
+pub mod check;
+pub mod error;
+pub mod feed;
+pub mod highlight;
+
+use mdsycx::{BodyRes, ParseRes};
+use serde::Deserialize;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{Display, Write},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{LazyLock, Mutex},
+};
+
+use crate::{build_cache, config::site_config, Routes, Version};
+
+use self::error::{ContentError, ContentErrorCause, Location};
+
+/// The directory that contains all the content.
+pub fn docs_dir() -> &'static Path {
+    &site_config().docs_dir
+}
+
+/// Errors that were encountered while loading content. These are collected instead of panicking
+/// so that all of them can be reported at once by [`load`].
+static ERRORS: Mutex<Vec<ContentError>> = Mutex::new(Vec::new());
+
+fn emit(err: ContentError) {
+    ERRORS.lock().unwrap().push(err);
+}
+
+/// Load all the content from [`docs_dir`].
+///
+/// Returns every error that was encountered while loading. If this returns `Ok`, all the docs
+/// and posts are guaranteed to be present in [`DOCS`] and [`POSTS`].
+pub fn load() -> Result<(), Vec<ContentError>> {
+    LazyLock::force(&VERSIONS);
+    LazyLock::force(&SECTIONS_JSON);
+    LazyLock::force(&DOCS);
+    LazyLock::force(&AUTHORS);
+    LazyLock::force(&POSTS);
+
+    let errors = std::mem::take(&mut *ERRORS.lock().unwrap());
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Frontmatter for a blog post.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PostFrontmatter {
+    pub title: String,
+    pub description: String,
+    #[serde(deserialize_with = "deserialize_date")]
+    pub date: Date,
+    /// The tags of the post, e.g. `release` or `tutorial`.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The GitHub handles of the authors of the post. Every author must be listed in
+    /// [`AUTHORS`].
+    #[serde(default)]
+    pub authors: Vec<String>,
+    /// Drafts are only rendered with `--drafts`.
+    #[serde(default)]
+    pub draft: bool,
+    /// Unlisted posts are rendered but are not listed anywhere, e.g. on the blog, in the feeds or
+    /// in the sitemap.
+    #[serde(default)]
+    pub unlisted: bool,
+    /// The post is only rendered once this date has passed at build time.
+    #[serde(default, deserialize_with = "deserialize_optional_date")]
+    pub publish_after: Option<Date>,
+}
+
+/// An author of blog posts.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Author {
+    /// The GitHub handle of the author. This is the key of the author in the authors file.
+    #[serde(skip)]
+    pub handle: String,
+    /// The full name of the author.
+    pub name: String,
+    /// The URL of the avatar of the author. If not set, the GitHub avatar is used.
+    #[serde(default)]
+    pub avatar: Option<String>,
+}
+
+impl Author {
+    pub fn avatar_url(&self) -> String {
+        self.avatar
+            .clone()
+            .unwrap_or_else(|| format!("https://github.com/{}.png", self.handle))
+    }
+
+    pub fn github_url(&self) -> String {
+        format!("https://github.com/{}", self.handle)
+    }
+
+    /// The URL of the list of the posts by this author.
+    pub fn href(&self) -> String {
+        format!("/blog/author/{}", self.handle)
+    }
+}
+
+/// Frontmatter for a documentation page.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DocFrontmatter {
+    /// The title of the doc page.
+    pub title: String,
+    /// Any subsections of the doc page.
+    #[serde(default)]
+    pub subsections: Vec<String>,
+    /// A short summary of the doc page for search engines and link previews. If not set, the
+    /// first paragraph of the page is used instead.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Represents a date in the format "YYYY-MM-DD".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Date {
+    pub day: u32,
+    pub month: u32,
+    pub year: u32,
+}
+
+impl PartialOrd for Date {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Date {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.year, self.month, self.day).cmp(&(other.year, other.month, other.day))
+    }
+}
+
+impl FromStr for Date {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        static INVALID: &str = "expected date in format YYYY-MM-DD";
+
+        let parts = s
+            .split('-')
+            .map(|x| x.parse())
+            .collect::<Result<Vec<u32>, _>>()
+            .map_err(|_| INVALID)?;
+        let [year, month, day] = parts[..] else {
+            return Err(INVALID);
+        };
+        if !(1..=12).contains(&month) {
+            return Err("month must be between 1 and 12");
+        }
+        if !(1..=days_in_month(year, month)).contains(&day) {
+            return Err("day does not exist in that month");
+        }
+        Ok(Date { day, month, year })
+    }
+}
+
+/// The number of days in the `month` (1-12) of the `year`.
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Date {
+    /// The current date in UTC.
+    pub fn today() -> Self {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("system time is before the epoch")
+            .as_secs();
+        Self::from_days_since_epoch((secs / 86400) as i64)
+    }
+
+    /// The inverse of [`Date::days_since_epoch`].
+    fn from_days_since_epoch(days: i64) -> Self {
+        // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let days = days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days - era * 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + (month <= 2) as i64;
+        Self {
+            day: day as u32,
+            month: month as u32,
+            year: year as u32,
+        }
+    }
+
+    /// The number of days since 1970-01-01.
+    fn days_since_epoch(&self) -> i64 {
+        // See http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+        let (month, day) = (self.month as i64, self.day as i64);
+        let year = self.year as i64 - (month <= 2) as i64;
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    /// Format the date as an RFC 2822 date-time at midnight UTC, as used by RSS.
+    pub fn to_rfc2822(&self) -> String {
+        static WEEKDAYS: &[&str] = &["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+        static MONTHS: &[&str] = &[
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+
+        let weekday = WEEKDAYS[self.days_since_epoch().rem_euclid(7) as usize];
+        let month = MONTHS[self.month as usize - 1];
+        format!(
+            "{weekday}, {:02} {month} {} 00:00:00 +0000",
+            self.day, self.year
+        )
+    }
+
+    /// Format the date as an RFC 3339 date-time at midnight UTC, as used by Atom.
+    pub fn to_rfc3339(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}T00:00:00Z",
+            self.year, self.month, self.day
+        )
+    }
+}
+
+/// Deserialize date in format "YYYY-MM-DD"
+fn deserialize_date<'de, D>(deserializer: D) -> Result<Date, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct DateVisitor;
+
+    impl serde::de::Visitor<'_> for DateVisitor {
+        type Value = Date;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a date in format YYYY-MM-DD")
+        }
+
+        // Parse the date inside the visitor so that the error points at the date itself.
+        fn visit_str<E: serde::de::Error>(self, s: &str) -> Result<Date, E> {
+            Date::from_str(s).map_err(E::custom)
+        }
+    }
+
+    deserializer.deserialize_str(DateVisitor)
+}
+
+/// Deserialize an optional date in format "YYYY-MM-DD". Use with `#[serde(default)]`.
+fn deserialize_optional_date<'de, D>(deserializer: D) -> Result<Option<Date>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserialize_date(deserializer).map(Some)
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        static MONTHS: &[&str] = &[
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ];
+
+        let month = MONTHS.get(self.month as usize - 1).unwrap_or(&"Error");
+
+        write!(f, "{month} {day}, {year}", day = self.day, year = self.year)
+    }
+}
+
+/// Represents the top-level sections of the book.
+///
+/// This is parsed from `sections.json`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SectionsJson {
+    pub sections: Vec<String>,
+}
+
+/// All the versions of the book, sorted from newest to oldest with `next` first.
+///
+/// Every directory in [`docs_dir`] that is named like a version and contains a `sections.json`
+/// file is a version of the book.
+pub static VERSIONS: LazyLock<Vec<Version>> = LazyLock::new(|| {
+    let entries = match fs::read_dir(docs_dir()) {
+        Ok(entries) => entries,
+        Err(err) => {
+            emit(ContentError::new(docs_dir(), ContentErrorCause::Io(err)));
+            return Vec::new();
+        }
+    };
+    let mut versions = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join("sections.json").is_file())
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<Version>().ok())
+        .collect::<Vec<_>>();
+    versions.sort();
+
+    if versions.is_empty() {
+        emit(ContentError::new(docs_dir(), ContentErrorCause::NoVersions));
+    }
+    versions
+});
+
+/// The version of the book that is served at `/book/...` without a version prefix. This is
+/// `next` if it exists, otherwise the newest version.
+pub static DEFAULT_VERSION: LazyLock<Version> = LazyLock::new(|| {
+    VERSIONS
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "next".parse().unwrap())
+});
+
+pub static SECTIONS_JSON: LazyLock<HashMap<Version, SectionsJson>> = LazyLock::new(|| {
+    let mut sections_json = HashMap::new();
+
+    for version in VERSIONS.iter() {
+        let path = docs_dir().join(version.as_str()).join("sections.json");
+        let sections = fs::read_to_string(&path)
+            .map_err(|err| ContentError::new(&path, ContentErrorCause::Io(err)))
+            .and_then(|json| {
+                serde_json::from_str(&json).map_err(|err| {
+                    let location = Location::from_line_column(&json, err.line(), err.column());
+                    ContentError::new(&path, ContentErrorCause::Json(err))
+                        .with_location(Some(location))
+                })
+            });
+        let sections = sections.unwrap_or_else(|err| {
+            emit(err);
+            SectionsJson::default()
+        });
+        sections_json.insert(version.clone(), sections);
+    }
+
+    sections_json
+});
+
+/// Get the events of a parsed markdown body.
+pub fn body_events(body: &BodyRes) -> Vec<mdsycx::Event> {
+    // The events are not public in mdsycx, so we go through serde to get them out.
+    #[derive(Deserialize)]
+    struct Body {
+        events: Vec<mdsycx::Event>,
+    }
+
+    let value = serde_json::to_value(body).expect("failed to serialize body");
+    serde_json::from_value::<Body>(value)
+        .expect("failed to deserialize body")
+        .events
+}
+
+/// Create a body from a list of events.
+pub fn body_from_events(events: Vec<mdsycx::Event>) -> BodyRes {
+    serde_json::from_value(serde_json::json!({ "events": events }))
+        .expect("failed to deserialize body")
+}
+
+/// Get the text of the first paragraph in the body, shortened to a length suitable for a page
+/// description.
+pub fn first_paragraph(body: &BodyRes) -> Option<String> {
+    const MAX_LEN: usize = 160;
+
+    let mut depth = 0;
+    let mut paragraph = None::<String>;
+    for event in body_events(body) {
+        match event {
+            mdsycx::Event::Start(tag) => {
+                if depth == 0 && tag == "p" {
+                    paragraph = Some(String::new());
+                }
+                depth += 1;
+            }
+            mdsycx::Event::Text(text) => {
+                if let Some(paragraph) = &mut paragraph {
+                    paragraph.push_str(&text);
+                }
+            }
+            mdsycx::Event::End => {
+                depth -= 1;
+                if depth == 0 && paragraph.is_some() {
+                    break;
+                }
+            }
+            mdsycx::Event::Attr(_, _) => {}
+        }
+    }
+
+    let paragraph = paragraph?.split_whitespace().collect::<Vec<_>>().join(" ");
+    if paragraph.is_empty() {
+        return None;
+    }
+    if paragraph.len() <= MAX_LEN {
+        return Some(paragraph);
+    }
+    // Cut at the last word that fits.
+    let mut end = MAX_LEN;
+    while !paragraph.is_char_boundary(end) {
+        end -= 1;
+    }
+    let end = paragraph[..end].rfind(' ').unwrap_or(end);
+    Some(format!("{}…", paragraph[..end].trim_end_matches([',', '.', ':', ';'])))
+}
+
+/// Rewrite the links to other book pages in the body so that they point to the same `version` of
+/// the book.
+pub fn versioned_book_links(body: &BodyRes, version: &Version) -> BodyRes {
+    let events = body_events(body)
+        .into_iter()
+        .map(|event| match event {
+            mdsycx::Event::Attr(key, value) if key == "href" => {
+                let value = match value.strip_prefix("/book/") {
+                    Some(rest)
+                        if rest
+                            .split(['/', '#'])
+                            .next()
+                            .is_none_or(|first| first.parse::<Version>().is_err()) =>
+                    {
+                        format!("/book/{version}/{rest}")
+                    }
+                    _ => value,
+                };
+                mdsycx::Event::Attr(key, value)
+            }
+            event => event,
+        })
+        .collect::<Vec<_>>();
+
+    body_from_events(events)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DocPage(Version, String, Option<String>);
+
+impl DocPage {
+    pub fn new(version: Version, section: String, doc: Option<String>) -> Self {
+        Self(version, section, doc)
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.0
+    }
+
+    pub fn section(&self) -> &str {
+        &self.1
+    }
+
+    pub fn subsection(&self) -> Option<&str> {
+        self.2.as_deref()
+    }
+
+    /// The path of the markdown file of this page, relative to [`docs_dir`] and without the
+    /// extension.
+    pub fn file_path(&self) -> String {
+        match self.subsection() {
+            Some(subsection) => format!("{}/{}/{subsection}", self.version(), self.section()),
+            None => format!("{}/{}", self.version(), self.section()),
+        }
+    }
+
+    /// The URL of this page. If `versioned` is `false`, the URL does not include the version.
+    pub fn href(&self, versioned: bool) -> String {
+        let prefix = if versioned {
+            format!("/book/{}", self.version())
+        } else {
+            "/book".to_string()
+        };
+        match self.subsection() {
+            Some(subsection) => format!("{prefix}/{}/{subsection}", self.section()),
+            None => format!("{prefix}/{}", self.section()),
+        }
+    }
+}
+
+fn doc_path(path: &str) -> PathBuf {
+    docs_dir().join(path).with_extension("md")
+}
+
+fn parse_doc<T>(path: &str) -> Result<ParseRes<T>, ContentError>
+where
+    T: for<'a> Deserialize<'a> + 'static,
+{
+    let full_path = doc_path(path);
+
+    let md = fs::read_to_string(&full_path)
+        .map_err(|err| ContentError::new(&full_path, ContentErrorCause::Io(err)))?;
+    mdsycx::parse(&md).map_err(|err| {
+        let location = match &err {
+            mdsycx::ParseError::DeserializeError(err) => err.location().map(|location| {
+                // The frontmatter starts right after the opening `---`.
+                let offset = md.len() - md.trim_start().len() + 3;
+                Location::from_index(&md, offset + location.index())
+            }),
+            mdsycx::ParseError::MissingFrontMatterEndDelimiter => Location::find(&md, "---"),
+        };
+        ContentError::new(&full_path, ContentErrorCause::Parse(err)).with_location(location)
+    })
+    .map(|mut parsed| {
+        parsed.body = highlight::highlight_code_blocks(&parsed.body);
+        parsed
+    })
+}
+
+/// Find where `subsection` is listed in the frontmatter of the doc at `path`.
+fn locate_subsection(path: &Path, subsection: &str) -> Option<Location> {
+    let md = fs::read_to_string(path).ok()?;
+    let start = md.find("subsections")?;
+    let index = start + md[start..].find(subsection)?;
+    Some(Location::from_index(&md, index))
+}
+
+pub static DOCS: std::sync::LazyLock<HashMap<DocPage, ParseRes<DocFrontmatter>>> =
+    LazyLock::new(|| {
+        let mut docs = HashMap::new();
+
+        // First parse all the top-level sections. Add any subsectiosn to a buffer to be parsed
+        // later.
+        let mut subsections = Vec::new();
+
+        for version in VERSIONS.iter() {
+            for section in SECTIONS_JSON[version].sections.iter() {
+                let page = DocPage::new(version.clone(), section.clone(), None);
+                let doc = match parse_doc::<DocFrontmatter>(&page.file_path()) {
+                    Ok(doc) => doc,
+                    Err(err) => {
+                        emit(err);
+                        continue;
+                    }
+                };
+                // Add subsections to buffer.
+                subsections.extend(
+                    doc.front_matter
+                        .subsections
+                        .iter()
+                        .map(|s| (page.clone(), s.clone())),
+                );
+                docs.insert(page, doc);
+            }
+        }
+
+        for (parent, subsection) in subsections {
+            let page = DocPage::new(
+                parent.version().clone(),
+                parent.section().to_string(),
+                Some(subsection.clone()),
+            );
+            let path = page.file_path();
+            match parse_doc::<DocFrontmatter>(&path) {
+                // Subsections don't have subsections.
+                Ok(doc) if !doc.front_matter.subsections.is_empty() => {
+                    let path = doc_path(&path);
+                    let location = fs::read_to_string(&path)
+                        .ok()
+                        .and_then(|md| Location::find(&md, "subsections"));
+                    emit(
+                        ContentError::new(path, ContentErrorCause::NestedSubsections)
+                            .with_location(location),
+                    );
+                }
+                Ok(doc) => {
+                    docs.insert(page, doc);
+                }
+                // Report missing subsections at the place where they are listed.
+                Err(ContentError {
+                    path,
+                    cause: ContentErrorCause::Io(source),
+                    ..
+                }) => {
+                    let parent = doc_path(&parent.file_path());
+                    let location = locate_subsection(&parent, &subsection);
+                    emit(
+                        ContentError::new(
+                            parent,
+                            ContentErrorCause::MissingSubsection {
+                                subsection,
+                                path,
+                                source,
+                            },
+                        )
+                        .with_location(location),
+                    );
+                }
+                Err(err) => emit(err),
+            }
+        }
+        docs
+    });
+
+/// Stores all the information of the structure of the book.
+#[derive(Debug, Clone)]
+pub struct BookIndex {
+    pub sections: Vec<BookSection>,
+    /// All the sections and subsections flattened in reading order.
+    pub pages: Vec<BookItem>,
+}
+
+impl BookIndex {
+    /// Get the pages that come before and after `page` in reading order.
+    pub fn neighbours(&self, page: &DocPage) -> (Option<&BookItem>, Option<&BookItem>) {
+        let Some(i) = self.pages.iter().position(|item| item.path == *page) else {
+            return (None, None);
+        };
+        let prev = i.checked_sub(1).and_then(|i| self.pages.get(i));
+        (prev, self.pages.get(i + 1))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BookSection {
+    pub title: String,
+    pub subsections: Vec<BookItem>,
+    pub path: DocPage,
+}
+
+#[derive(Debug, Clone)]
+pub struct BookItem {
+    pub title: String,
+    pub path: DocPage,
+}
+
+/// The index of the book for each version.
+pub static BOOK_INDEX: LazyLock<HashMap<Version, BookIndex>> = LazyLock::new(|| {
+    VERSIONS
+        .iter()
+        .map(|version| (version.clone(), book_index(version)))
+        .collect()
+});
+
+fn book_index(version: &Version) -> BookIndex {
+    let mut sections = vec![];
+
+    for section in SECTIONS_JSON[version].sections.iter() {
+        let path = DocPage::new(version.clone(), section.clone(), None);
+        let doc = DOCS.get(&path).expect("failed to get doc");
+        let title = doc.front_matter.title.clone();
+        let subsections = doc
+            .front_matter
+            .subsections
+            .iter()
+            .map(|subsection| {
+                let path = DocPage::new(version.clone(), section.clone(), Some(subsection.clone()));
+                let doc = DOCS.get(&path).expect("failed to get doc");
+                let title = doc.front_matter.title.clone();
+                BookItem { title, path }
+            })
+            .collect();
+
+        sections.push(BookSection {
+            title,
+            subsections,
+            path,
+        })
+    }
+
+    let pages = sections
+        .iter()
+        .flat_map(|section| {
+            let item = BookItem {
+                title: section.title.clone(),
+                path: section.path.clone(),
+            };
+            std::iter::once(item).chain(section.subsections.iter().cloned())
+        })
+        .collect();
+
+    BookIndex { sections, pages }
+}
+
+/// The files that the authors can be listed in, relative to [`docs_dir`]. Only the first one that
+/// exists is used.
+static AUTHORS_FILES: &[&str] = &["authors.toml", "authors.json"];
+
+/// All the authors of blog posts by their GitHub handle.
+///
+/// These are listed in `authors.toml` or `authors.json` in [`docs_dir`]. If neither exists, there
+/// are no authors.
+pub static AUTHORS: LazyLock<BTreeMap<String, Author>> = LazyLock::new(|| {
+    let Some(path) = AUTHORS_FILES
+        .iter()
+        .map(|file| docs_dir().join(file))
+        .find(|path| path.is_file())
+    else {
+        return BTreeMap::new();
+    };
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(err) => {
+            emit(ContentError::new(path, ContentErrorCause::Io(err)));
+            return BTreeMap::new();
+        }
+    };
+    let authors = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str::<BTreeMap<String, Author>>(&source).map_err(|err| {
+            let location = Location::from_line_column(&source, err.line(), err.column());
+            ContentError::new(&path, ContentErrorCause::Json(err)).with_location(Some(location))
+        })
+    } else {
+        toml::from_str::<BTreeMap<String, Author>>(&source).map_err(|err| {
+            let location = err
+                .span()
+                .map(|span| Location::from_index(&source, span.start));
+            ContentError::new(&path, ContentErrorCause::Toml(Box::new(err))).with_location(location)
+        })
+    };
+    match authors {
+        Ok(mut authors) => {
+            for (handle, author) in &mut authors {
+                author.handle = handle.clone();
+            }
+            authors
+        }
+        Err(err) => {
+            emit(err);
+            BTreeMap::new()
+        }
+    }
+});
+
+/// Get the authors of a post. Authors that are not in [`AUTHORS`] are skipped, but those are
+/// already reported when the posts are loaded.
+pub fn post_authors(post: &PostFrontmatter) -> Vec<Author> {
+    post.authors
+        .iter()
+        .filter_map(|handle| AUTHORS.get(handle).cloned())
+        .collect()
+}
+
+pub static POSTS: std::sync::LazyLock<HashMap<String, ParseRes<PostFrontmatter>>> =
+    LazyLock::new(|| {
+        let mut posts = HashMap::new();
+        // Drafts and scheduled posts are included so that their tags are already checked before
+        // they are published.
+        let mut tagged = Vec::new();
+
+        let post_dir = docs_dir().join("posts");
+        let entries = match fs::read_dir(&post_dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                emit(ContentError::new(post_dir, ContentErrorCause::Io(err)));
+                return posts;
+            }
+        };
+        for entry in entries {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(err) => {
+                    emit(ContentError::new(&post_dir, ContentErrorCause::Io(err)));
+                    continue;
+                }
+            };
+            let Some(name) = path.file_stem() else {
+                continue;
+            };
+            let name = name.to_string_lossy();
+
+            match parse_doc::<PostFrontmatter>(&format!("posts/{name}")) {
+                Ok(post) => {
+                    for author in &post.front_matter.authors {
+                        if !AUTHORS.contains_key(author) {
+                            let location = fs::read_to_string(&path)
+                                .ok()
+                                .and_then(|md| Location::find(&md, author));
+                            emit(
+                                ContentError::new(
+                                    &path,
+                                    ContentErrorCause::UnknownAuthor(author.clone()),
+                                )
+                                .with_location(location),
+                            );
+                        }
+                    }
+                    tagged.push((path.clone(), post.front_matter.tags.clone()));
+                    if post.front_matter.draft && !site_config().drafts {
+                        eprintln!("Skipping draft `{name}`, use `--drafts` to render it");
+                        continue;
+                    }
+                    if let Some(date) = post.front_matter.publish_after {
+                        if date > Date::today() {
+                            eprintln!("Skipping `{name}`, which is scheduled for {date}");
+                            continue;
+                        }
+                    }
+                    posts.insert(name.to_string(), post);
+                }
+                Err(err) => emit(err),
+            }
+        }
+        check_tags(tagged);
+
+        posts
+    });
+
+/// Report the tags that have no slug, and the tags that have the same slug as a differently
+/// written tag, since they would otherwise be merged into one tag page.
+fn check_tags(mut tagged: Vec<(PathBuf, Vec<String>)>) {
+    // Sort so that the first post that uses a slug does not depend on the order of the files.
+    tagged.sort();
+    let mut seen = HashMap::<String, (&str, &Path)>::new();
+    for (path, tags) in &tagged {
+        for tag in tags {
+            let location = || {
+                fs::read_to_string(path)
+                    .ok()
+                    .and_then(|md| Location::find(&md, tag))
+            };
+            let slug = tag_slug(tag);
+            if slug.is_empty() {
+                emit(
+                    ContentError::new(path, ContentErrorCause::EmptyTagSlug(tag.clone()))
+                        .with_location(location()),
+                );
+                continue;
+            }
+            match seen.get(&slug) {
+                Some((other, first)) if *other != tag => emit(
+                    ContentError::new(
+                        path,
+                        ContentErrorCause::TagCollision {
+                            other: other.to_string(),
+                            first: first.to_path_buf(),
+                        },
+                    )
+                    .with_location(location()),
+                ),
+                Some(_) => {}
+                None => {
+                    seen.insert(slug, (tag, path));
+                }
+            }
+        }
+    }
+}
+
+/// Get all the posts that are not unlisted, sorted from newest to oldest.
+pub fn sorted_posts() -> Vec<(&'static String, &'static ParseRes<PostFrontmatter>)> {
+    let mut posts = POSTS
+        .iter()
+        .filter(|(_, post)| !post.front_matter.unlisted)
+        .collect::<Vec<_>>();
+    posts.sort_by(|(a_id, a), (b_id, b)| {
+        (b.front_matter.date, b_id).cmp(&(a.front_matter.date, a_id))
+    });
+    posts
+}
+
+/// A tag that posts can be filtered by.
+#[derive(Debug, Clone)]
+pub struct Tag {
+    /// The name of the tag as it is written in the frontmatter.
+    pub name: String,
+    /// The ids of the posts with this tag, sorted from newest to oldest.
+    pub posts: Vec<String>,
+}
+
+/// Get the slug of a tag that is used in URLs, e.g. `Getting Started` becomes `getting-started`.
+pub fn tag_slug(tag: &str) -> String {
+    tag.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// All the tags of the posts by their slug. Tags without a slug and differently written tags with
+/// the same slug are rejected when the posts are loaded.
+pub static TAGS: LazyLock<BTreeMap<String, Tag>> = LazyLock::new(|| {
+    let mut tags = BTreeMap::<String, Tag>::new();
+    for (id, post) in sorted_posts() {
+        for name in &post.front_matter.tags {
+            let tag = tags.entry(tag_slug(name)).or_insert_with(|| Tag {
+                name: name.clone(),
+                posts: Vec::new(),
+            });
+            if !tag.posts.contains(id) {
+                tag.posts.push(id.clone());
+            }
+        }
+    }
+    tags
+});
+
+/// Get the ids of the posts by the author with the given handle, sorted from newest to oldest.
+pub fn author_posts(handle: &str) -> Vec<&'static String> {
+    sorted_posts()
+        .into_iter()
+        .filter(|(_, post)| post.front_matter.authors.iter().any(|author| author == handle))
+        .map(|(id, _)| id)
+        .collect()
+}
+
+/// The number of posts that are listed on each page of the blog.
+pub const POSTS_PER_PAGE: usize = 10;
+
+/// The number of pages of the blog. There is always at least one page, even if there are no
+/// posts.
+pub fn blog_page_count() -> u32 {
+    sorted_posts().len().div_ceil(POSTS_PER_PAGE).max(1) as u32
+}
+
+/// The URL of the `page`th page of the blog, starting from 1. The first page is served at
+/// `/blog`.
+pub fn blog_page_href(page: u32) -> String {
+    if page <= 1 {
+        "/blog".to_string()
+    } else {
+        format!("/blog/{page}")
+    }
+}
+
+/// Estimate how many minutes it takes to read the body, rounded up.
+pub fn reading_time(body: &BodyRes) -> u32 {
+    const WORDS_PER_MINUTE: usize = 200;
+
+    let words = body_events(body)
+        .iter()
+        .map(|event| match event {
+            mdsycx::Event::Text(text) => text.split_whitespace().count(),
+            _ => 0,
+        })
+        .sum::<usize>();
+    words.div_ceil(WORDS_PER_MINUTE).max(1) as u32
+}
+
+pub fn get_static_paths() -> Vec<(Routes, String)> {
+    let mut paths = vec![];
+
+    paths.push((Routes::Index, "/index.html".to_string()));
+    paths.push((Routes::NotFound, "/404.html".to_string()));
+
+    for post in POSTS.keys() {
+        paths.push((Routes::Post(post.clone()), format!("/post/{post}.html")));
+    }
+
+    paths.push((Routes::Blog, "/blog.html".to_string()));
+    for page in 2..=blog_page_count() {
+        paths.push((Routes::BlogPage(page), format!("{}.html", blog_page_href(page))));
+    }
+    for tag in TAGS.keys() {
+        paths.push((Routes::BlogTag(tag.clone()), format!("/blog/tag/{tag}.html")));
+    }
+    // Only authors that have written a post get a page.
+    for author in AUTHORS.values() {
+        if !author_posts(&author.handle).is_empty() {
+            paths.push((
+                Routes::BlogAuthor(author.handle.clone()),
+                format!("{}.html", author.href()),
+            ));
+        }
+    }
+
+    for page in DOCS.keys() {
+        let route = match page.subsection() {
+            Some(subsection) => Routes::VersionedBookSubsection(
+                page.version().clone(),
+                page.section().to_string(),
+                subsection.to_string(),
+            ),
+            None => {
+                Routes::VersionedBookSection(page.version().clone(), page.section().to_string())
+            }
+        };
+        paths.push((route, format!("{}.html", page.href(true))));
+
+        // The default version is also served without the version prefix.
+        if *page.version() == *DEFAULT_VERSION {
+            let route = match page.subsection() {
+                Some(subsection) => {
+                    Routes::BookSubsection(page.section().to_string(), subsection.to_string())
+                }
+                None => Routes::BookSection(page.section().to_string()),
+            };
+            paths.push((route, format!("{}.html", page.href(false))));
+        }
+    }
+
+    // Keep the output, e.g. the sitemap, the same between builds.
+    paths.sort_by(|(_, a), (_, b)| a.cmp(b));
+    paths
+}
+
+/// The structure of the book, which is shown in the sidebar, the version switcher and the
+/// previous/next links of every book page.
+static BOOK_FINGERPRINT: LazyLock<u64> = LazyLock::new(|| {
+    let mut book = Vec::new();
+    for version in VERSIONS.iter() {
+        let index = &BOOK_INDEX[version];
+        for section in &index.sections {
+            book.push((section.path.href(true), section.title.as_str()));
+            for item in &section.subsections {
+                book.push((item.path.href(true), item.title.as_str()));
+            }
+        }
+    }
+    build_cache::fingerprint((DEFAULT_VERSION.as_str(), book))
+});
+
+/// Hash everything that the page for `route` is rendered from, so that it is only rendered again
+/// if something has changed.
+pub fn route_fingerprint(route: &Routes) -> u64 {
+    let source = |path: &str| fs::read(doc_path(path)).ok();
+    let doc_source = |version: Option<&Version>, section: &str, doc: Option<&str>| {
+        let version = version.unwrap_or(&DEFAULT_VERSION).clone();
+        let page = DocPage::new(version, section.to_string(), doc.map(str::to_string));
+        (source(&page.file_path()), *BOOK_FINGERPRINT)
+    };
+
+    // Posts show the names and avatars of their authors.
+    let authors_source = || {
+        AUTHORS_FILES
+            .iter()
+            .map(|file| fs::read(docs_dir().join(file)).ok())
+            .collect::<Vec<_>>()
+    };
+    // The sources of all the posts, for pages that list them.
+    let posts_source = || {
+        let mut posts = POSTS.keys().collect::<Vec<_>>();
+        posts.sort();
+        let posts = posts
+            .into_iter()
+            .map(|id| (id, source(&format!("posts/{id}"))))
+            .collect::<Vec<_>>();
+        (posts, authors_source())
+    };
+
+    let route_str = format!("{route:?}");
+    match route {
+        Routes::Index => {
+            // The index page shows the stats of the repository and a list of the latest posts.
+            let stats = serde_json::to_string(&(
+                crate::api_stats::get_latest_release(),
+                crate::api_stats::get_repo_stats(),
+                crate::api_stats::get_contributors(),
+                crate::api_stats::get_crate_io_stats(),
+            ))
+            .expect("failed to serialize stats");
+            build_cache::fingerprint((route_str, stats, posts_source()))
+        }
+        Routes::Post(id) => {
+            // Tags and authors are only linked if they have a page, which depends on the other
+            // posts, e.g. for an unlisted post.
+            let listed = POSTS.get(id).map(|post| {
+                let tags = post
+                    .front_matter
+                    .tags
+                    .iter()
+                    .map(|tag| TAGS.contains_key(&tag_slug(tag)))
+                    .collect::<Vec<_>>();
+                let authors = post
+                    .front_matter
+                    .authors
+                    .iter()
+                    .map(|handle| !author_posts(handle).is_empty())
+                    .collect::<Vec<_>>();
+                (tags, authors)
+            });
+            build_cache::fingerprint((
+                route_str,
+                source(&format!("posts/{id}")),
+                authors_source(),
+                listed,
+            ))
+        }
+        Routes::Blog | Routes::BlogPage(_) | Routes::BlogTag(_) | Routes::BlogAuthor(_) => {
+            build_cache::fingerprint((route_str, posts_source()))
+        }
+        Routes::BookSection(section) => {
+            build_cache::fingerprint((route_str, doc_source(None, section, None)))
+        }
+        Routes::VersionedBookSection(version, section) => {
+            build_cache::fingerprint((route_str, doc_source(Some(version), section, None)))
+        }
+        Routes::BookSubsection(section, doc) => {
+            build_cache::fingerprint((route_str, doc_source(None, section, Some(doc))))
+        }
+        Routes::VersionedBookSubsection(version, section, doc) => build_cache::fingerprint((
+            route_str,
+            doc_source(Some(version), section, Some(doc)),
+        )),
+        Routes::NotFound => build_cache::fingerprint(route_str),
+    }
+}
+
+/// Generate an XML sitemap file.
+pub fn generate_sitemap_xml() -> Result<String, std::fmt::Error> {
+    let paths = get_static_paths();
+    let mut buf = String::new();
+
+    write!(
+        &mut buf,
+        r#"<?xml version="1.0" encoding="UTF-8"?><urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#
+    )?;
+
+    for (route, path) in paths {
+        match &route {
+            Routes::NotFound => continue,
+            Routes::Post(id) if POSTS[id].front_matter.unlisted => continue,
+            // The default version of the book is also served without the version, which is the
+            // canonical URL.
+            Routes::VersionedBookSection(version, _)
+            | Routes::VersionedBookSubsection(version, _, _)
+                if *version == *DEFAULT_VERSION =>
+            {
+                continue
+            }
+            _ => {}
+        }
+        let path = path
+            .strip_suffix(".html")
+            .expect("should be an html page")
+            .trim_end_matches("index");
+        let loc = format!("{}{path}", site_config().base_url);
+
+        write!(&mut buf, r#"<url><loc>{loc}</loc></url>"#)?;
+    }
+
+    write!(&mut buf, r#"</urlset>"#)?;
+
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dates() {
+        let date = "2024-11-01".parse::<Date>().unwrap();
+        assert_eq!(
+            date,
+            Date {
+                day: 1,
+                month: 11,
+                year: 2024
+            }
+        );
+        assert!("2024-12-31".parse::<Date>().is_ok());
+        assert!("2024-13-01".parse::<Date>().is_err());
+        assert!("2024-01-00".parse::<Date>().is_err());
+        assert!("2024-01-32".parse::<Date>().is_err());
+        assert!("2024-01".parse::<Date>().is_err());
+        assert!("yesterday".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn parse_dates_checks_the_length_of_the_month() {
+        assert!("2023-04-30".parse::<Date>().is_ok());
+        assert!("2023-04-31".parse::<Date>().is_err());
+        assert!("2023-06-31".parse::<Date>().is_err());
+        assert!("2023-02-28".parse::<Date>().is_ok());
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("2024-02-31".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn parse_dates_in_leap_years() {
+        assert!("2024-02-29".parse::<Date>().is_ok());
+        assert!("2024-02-30".parse::<Date>().is_err());
+        // Centuries are only leap years if they are divisible by 400.
+        assert!("1900-02-29".parse::<Date>().is_err());
+        assert!("2000-02-29".parse::<Date>().is_ok());
+    }
+
+    #[test]
+    fn format_dates() {
+        let date = "2024-02-29".parse::<Date>().unwrap();
+        assert_eq!(date.to_rfc2822(), "Thu, 29 Feb 2024 00:00:00 +0000");
+        assert_eq!(date.to_rfc3339(), "2024-02-29T00:00:00Z");
+        assert_eq!(Date::from_days_since_epoch(date.days_since_epoch()), date);
+    }
+}
@@ -1,10 +1,12 @@
 use sycamore::prelude::*;
 
-use crate::{CurrentRoute, Routes};
+use crate::{config::site_config, search::SearchBox, CurrentRoute, Routes};
 
 #[component(inline_props)]
 fn Header(show_menu: ReadSignal<bool>, menu_open: Signal<bool>) -> View {
     let toggle_menu = move |_| menu_open.set(!menu_open.get());
+    let repository_url = site_config().repository_url();
+    let discord = site_config().discord.clone();
     view! {
         header(class="fixed top-0 z-50 w-full border-b-2 border-gray-200 bg-gray-100 dark:bg-gray-900 dark:border-gray-800") {
             nav(class="px-4") {
@@ -28,17 +30,23 @@ fn Header(show_menu: ReadSignal<bool>, menu_open: Signal<bool>) -> View {
                             "Sycamore"
                         }
                     }
-                    div(class="flex flex-row space-x-6 text-xl *:hover:text-gray-600 dark:*:hover:text-gray-300") {
-                        a(href="/book/introduction") {
-                            i(class="bi bi-book-half", aria-label="Book")
-                        }
-                        a(href="https://github.com/sycamore-rs/sycamore") {
-                            i(class="bi bi-github", aria-label="GitHub")
-                        }
-                        a(href="https://discord.gg/vDwFUmm6mU") {
-                            i(class="bi bi-discord", aria-label="Discord")
+                    div(class="flex flex-row items-center gap-6") {
+                        SearchBox {}
+                        div(class="flex flex-row space-x-6 text-xl *:hover:text-gray-600 dark:*:hover:text-gray-300") {
+                            a(href="/book/introduction") {
+                                i(class="bi bi-book-half", aria-label="Book")
+                            }
+                            a(href="/blog") {
+                                i(class="bi bi-newspaper", aria-label="Blog")
+                            }
+                            a(href=repository_url) {
+                                i(class="bi bi-github", aria-label="GitHub")
+                            }
+                            a(href=discord) {
+                                i(class="bi bi-discord", aria-label="Discord")
+                            }
+                            DarkModeToggle {}
                         }
-                        DarkModeToggle {}
                     }
                 }
             }
@@ -71,6 +79,8 @@ fn DarkModeToggle() -> View {
 
 #[component]
 fn Footer() -> View {
+    let repository_url = site_config().repository_url();
+    let discord = site_config().discord.clone();
     view! {
         footer(class="text-sm px-4 md:px-22 pt-6 pb-10 border-t-2 border-gray-200 bg-gray-100 dark:bg-gray-900 dark:border-gray-800") {
             div(class="flex flex-col sm:flex-row gap-10 md:gap-20 lg:gap-40") {
@@ -89,10 +99,10 @@ fn Footer() -> View {
                     p(class="font-semibold my-2") { "Community" }
                     ul {
                         li {
-                            a(href="https://github.com/sycamore-rs/sycamore") { "GitHub" }
+                            a(href=repository_url) { "GitHub" }
                         }
                         li {
-                            a(href="https://discord.gg/vDwFUmm6mU") { "Discord" }
+                            a(href=discord) { "Discord" }
                         }
                         li {
                             a(href="https://github.com/sycamore-rs/awesome-sycamore") { "Awesome" }
@@ -105,6 +115,9 @@ fn Footer() -> View {
                         li {
                             a(href="/book/introduction") { "Book" }
                         }
+                        li {
+                            a(href="/blog") { "Blog" }
+                        }
                         li {
                             a(href="https://docs.rs/sycamore") { "docs.rs" }
                         }
@@ -121,11 +134,19 @@ pub fn Layout(children: Children) -> View {
     let current_route = use_context::<CurrentRoute>();
 
     let menu_open = create_signal(false);
+    // Close the menu when navigating to another page.
+    create_effect(move || {
+        current_route.0.track();
+        menu_open.set(false);
+    });
     // Show the menu only on book pages.
     let is_book_page = create_selector(move || {
         matches!(
             current_route.0.get_clone(),
-            Routes::BookSubsection(_, _) | Routes::BookSection(_)
+            Routes::BookSubsection(_, _)
+                | Routes::BookSection(_)
+                | Routes::VersionedBookSubsection(_, _, _)
+                | Routes::VersionedBookSection(_, _)
         )
     });
 
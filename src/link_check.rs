@@ -0,0 +1,374 @@
+//! Check that all the internal links in the rendered site resolve to an existing page or file.
+//!
+//! This runs after all the pages and server components have been written to disk.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    content,
+    server_component::{fragment_path, Manifest},
+    Routes,
+};
+
+/// A start tag found in an HTML document, along with its attributes.
+struct Tag<'a> {
+    name: &'a str,
+    attrs: Vec<(&'a str, String)>,
+}
+
+impl Tag<'_> {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A minimal HTML tokenizer that only extracts start tags and their attributes. This is good
+/// enough for the HTML that we generate ourselves.
+fn parse_tags(html: &str) -> Vec<Tag<'_>> {
+    let mut tags = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        if rest.starts_with("</") || rest.starts_with("<!") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+
+        let name_len = rest[1..]
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+            .unwrap_or(rest.len() - 1);
+        if name_len == 0 {
+            rest = &rest[1..];
+            continue;
+        }
+        let name = &rest[1..1 + name_len];
+        rest = &rest[1 + name_len..];
+
+        let mut attrs = Vec::new();
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+            if rest.is_empty() || rest.starts_with('>') {
+                rest = rest.get(1..).unwrap_or_default();
+                break;
+            }
+            let key_len = rest
+                .find(|c: char| c.is_ascii_whitespace() || c == '=' || c == '>' || c == '/')
+                .unwrap_or(rest.len());
+            let key = &rest[..key_len];
+            rest = &rest[key_len..];
+
+            let value = if let Some(value) = rest.strip_prefix('=') {
+                let (value, remaining) = match value.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let value = &value[1..];
+                        let end = value.find(quote).unwrap_or(value.len());
+                        (&value[..end], value.get(end + 1..).unwrap_or_default())
+                    }
+                    _ => {
+                        let end = value
+                            .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                            .unwrap_or(value.len());
+                        (&value[..end], &value[end..])
+                    }
+                };
+                rest = remaining;
+                unescape(value)
+            } else {
+                String::new()
+            };
+            attrs.push((key, value));
+        }
+
+        // Skip over the contents of raw text elements.
+        if name.eq_ignore_ascii_case("script") || name.eq_ignore_ascii_case("style") {
+            let end_tag = format!("</{name}");
+            rest = rest.find(&end_tag).map_or("", |end| &rest[end..]);
+        }
+
+        tags.push(Tag { name, attrs });
+    }
+    tags
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Resolve `link` relative to the page at `base`. Returns the path and the fragment of the link,
+/// or `None` if the link is external.
+fn resolve(base: &str, link: &str) -> Option<(String, Option<String>)> {
+    // Links with a scheme (e.g. `https:`, `mailto:`) or protocol-relative links are external.
+    let scheme_end = link.find([':', '/', '?', '#']);
+    if link.starts_with("//") || scheme_end.is_some_and(|i| link[i..].starts_with(':')) {
+        return None;
+    }
+
+    let (link, fragment) = match link.split_once('#') {
+        Some((link, fragment)) => (link, Some(fragment.to_string())),
+        None => (link, None),
+    };
+    let link = link.split_once('?').map_or(link, |(link, _)| link);
+
+    let path = if link.is_empty() {
+        base.to_string()
+    } else if link.starts_with('/') {
+        link.to_string()
+    } else {
+        let dir = base.rsplit_once('/').map_or("", |(dir, _)| dir);
+        format!("{dir}/{link}")
+    };
+
+    // Normalize `.` and `..` segments.
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Some((format!("/{}", segments.join("/")), fragment))
+}
+
+/// Get the URL of a page from its output path, e.g. `/book/introduction.html` becomes
+/// `/book/introduction`.
+fn page_url(path: &str) -> String {
+    let url = path.strip_suffix(".html").unwrap_or(path);
+    match url.strip_suffix("index") {
+        Some(url) => url.to_string(),
+        None => url.to_string(),
+    }
+}
+
+/// A link that does not resolve to anything.
+#[derive(Debug)]
+pub struct DeadLink {
+    /// The HTML file that contains the link.
+    pub file: PathBuf,
+    /// The link as it appears in the HTML.
+    pub link: String,
+    /// The resolved path of the link.
+    pub target: String,
+    /// The fragment of the link, if the page exists but the anchor does not.
+    pub missing_anchor: Option<String>,
+}
+
+impl Display for DeadLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "error: dead link `{}` in `{}`: ",
+            self.link,
+            self.file.display()
+        )?;
+        match &self.missing_anchor {
+            Some(anchor) => write!(f, "anchor `#{anchor}` does not exist on `{}`", self.target),
+            None => write!(f, "`{}` does not exist", self.target),
+        }
+    }
+}
+
+/// Print all the dead links to stderr, followed by a summary line.
+pub fn report(dead_links: &[DeadLink]) {
+    for dead_link in dead_links {
+        eprintln!("{dead_link}");
+    }
+    match dead_links.len() {
+        1 => eprintln!("error: aborting due to 1 dead link"),
+        n => eprintln!("error: aborting due to {n} dead links"),
+    }
+}
+
+/// Check all the links in the rendered pages and server components under `public_path`. The
+/// server components are found through the `manifest`.
+pub fn check_links(public_path: &Path, manifest: &Manifest) -> Vec<DeadLink> {
+    // All the pages that we have generated, along with their HTML.
+    let mut pages = Vec::new();
+    // The anchors that exist on each page.
+    let mut anchors = HashMap::<String, HashSet<String>>::new();
+
+    for (page, doc) in content::DOCS.iter() {
+        let ids = doc.headings.iter().map(|heading| heading.id.clone());
+        anchors.entry(page.href(true)).or_default().extend(ids);
+        if *page.version() == *content::DEFAULT_VERSION {
+            let ids = doc.headings.iter().map(|heading| heading.id.clone());
+            anchors.entry(page.href(false)).or_default().extend(ids);
+        }
+    }
+    for (id, post) in content::POSTS.iter() {
+        let ids = post.headings.iter().map(|heading| heading.id.clone());
+        anchors
+            .entry(format!("/post/{id}"))
+            .or_default()
+            .extend(ids);
+    }
+
+    for (route, path) in content::get_static_paths() {
+        let url = page_url(&path);
+        let file = public_path.join(path.trim_start_matches('/'));
+        let Ok(html) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let ids = anchors.entry(url.clone()).or_default();
+        ids.extend(
+            parse_tags(&html)
+                .iter()
+                .filter_map(|tag| tag.attr("id"))
+                .map(ToString::to_string),
+        );
+        // The not found page is rendered at `/404` but is served at any path.
+        if route != Routes::NotFound {
+            pages.push((file, url, html));
+        }
+    }
+
+    // Find the page that each server component is rendered on so that we can resolve relative
+    // links inside of it.
+    let mut server_components = Vec::new();
+    for (_, url, html) in &pages {
+        for tag in parse_tags(html) {
+            if tag.name == "server-component" {
+                if let Some((id, hash)) = tag
+                    .attr("data-component")
+                    .and_then(|id| manifest.get_key_value(id))
+                {
+                    let file = public_path.join(fragment_path(id, hash).trim_start_matches('/'));
+                    if let Ok(html) = fs::read_to_string(&file) {
+                        server_components.push((file, url.clone(), html));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut dead_links = Vec::new();
+    // The HTML of the server components is also part of the pages that they are rendered on, so
+    // only check each link once per page.
+    let mut checked = HashSet::<(&str, String)>::new();
+    for (file, url, html) in pages.iter().chain(server_components.iter()) {
+        for tag in parse_tags(html) {
+            for (_, link) in tag
+                .attrs
+                .iter()
+                .filter(|(key, _)| *key == "href" || *key == "src")
+            {
+                if !checked.insert((url, link.clone())) {
+                    continue;
+                }
+                let Some((target, fragment)) = resolve(url, link) else {
+                    continue;
+                };
+                let missing_anchor = match anchors.get(&target) {
+                    Some(ids) => fragment.filter(|f| !f.is_empty() && !ids.contains(f)),
+                    // Static files such as images and stylesheets.
+                    None if public_path.join(target.trim_start_matches('/')).is_file() => None,
+                    None => {
+                        dead_links.push(DeadLink {
+                            file: file.clone(),
+                            link: link.clone(),
+                            target,
+                            missing_anchor: None,
+                        });
+                        continue;
+                    }
+                };
+                if let Some(anchor) = missing_anchor {
+                    dead_links.push(DeadLink {
+                        file: file.clone(),
+                        link: link.clone(),
+                        target,
+                        missing_anchor: Some(anchor),
+                    });
+                }
+            }
+        }
+    }
+
+    dead_links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(path: &str) -> Option<(String, Option<String>)> {
+        Some((path.to_string(), None))
+    }
+
+    #[test]
+    fn parse_tags_and_attributes() {
+        let html = r#"<!DOCTYPE html><a href="/a?x=1&amp;y=2" class=link>A</a><img src='b.png'/>"#;
+        let tags = parse_tags(html);
+        let names = tags.iter().map(|tag| tag.name).collect::<Vec<_>>();
+        assert_eq!(names, ["a", "img"]);
+        assert_eq!(tags[0].attr("href"), Some("/a?x=1&y=2"));
+        assert_eq!(tags[0].attr("class"), Some("link"));
+        assert_eq!(tags[1].attr("src"), Some("b.png"));
+        assert_eq!(tags[1].attr("href"), None);
+    }
+
+    #[test]
+    fn parse_tags_skips_comments_and_raw_text() {
+        let html = r#"<!-- <a href="/comment"> --><script>if (a <b) {}</script><style>a<b{}</style><p id=x hidden>"#;
+        let tags = parse_tags(html);
+        let names = tags.iter().map(|tag| tag.name).collect::<Vec<_>>();
+        assert_eq!(names, ["script", "style", "p"]);
+        assert_eq!(tags[2].attr("id"), Some("x"));
+        assert_eq!(tags[2].attr("hidden"), Some(""));
+    }
+
+    #[test]
+    fn resolve_relative_paths() {
+        assert_eq!(resolve("/book/next/intro", "guide"), path("/book/next/guide"));
+        assert_eq!(resolve("/book/next/intro", "./guide"), path("/book/next/guide"));
+        assert_eq!(resolve("/book/next/intro", "/blog"), path("/blog"));
+        assert_eq!(resolve("/book/next/intro", "../v0.8/intro"), path("/book/v0.8/intro"));
+        assert_eq!(resolve("/book/intro", "../../../x"), path("/x"));
+    }
+
+    #[test]
+    fn resolve_fragments_and_queries() {
+        assert_eq!(
+            resolve("/book/intro", "#setup"),
+            Some(("/book/intro".to_string(), Some("setup".to_string())))
+        );
+        assert_eq!(
+            resolve("/book/intro", "guide?tab=1#usage"),
+            Some(("/book/guide".to_string(), Some("usage".to_string())))
+        );
+        assert_eq!(resolve("/blog", "?page=2"), path("/blog"));
+    }
+
+    #[test]
+    fn resolve_external_links() {
+        assert_eq!(resolve("/", "https://sycamore.dev/book"), None);
+        assert_eq!(resolve("/", "//example.com/a"), None);
+        assert_eq!(resolve("/", "mailto:a@example.com"), None);
+        // A colon after the first path segment is not a scheme.
+        assert_eq!(resolve("/", "/a:b"), path("/a:b"));
+    }
+
+    #[test]
+    fn page_urls() {
+        assert_eq!(page_url("/index.html"), "/");
+        assert_eq!(page_url("/book/introduction.html"), "/book/introduction");
+    }
+}
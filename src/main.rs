@@ -6,52 +6,135 @@ cfg_ssr_item!(
 cfg_ssr_item! {
     pub mod api_stats;
 }
+cfg_ssr_item! {
+    pub mod build_cache;
+}
+pub mod config;
+cfg_ssr_item! {
+    pub mod link_check;
+}
+cfg_ssr_item! {
+    pub mod og_image;
+}
 pub mod layout;
+pub mod navigation;
 pub mod pages;
+pub mod prefetch;
+pub mod search;
 pub mod server_component;
 pub mod shell;
 pub mod utils;
 
 use self::shell::*;
 
-#[cfg_ssr]
-pub static DOCS_DIR: &str = match option_env!("DOCS_DIR") {
-    Some(path) => path,
-    None => "sycamore/docs/",
-};
-
 #[cfg_ssr]
 #[tokio::main]
 async fn main() {
-    use std::{fs, path::PathBuf};
+    use std::{
+        fs,
+        num::NonZeroUsize,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
 
-    static PUBLIC_PATH: &str = "dist/.stage";
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        eprintln!("{}", config::USAGE);
+        return;
+    }
+    let cli = match config::Cli::parse(args) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("error: {err}");
+            eprintln!("{}", config::USAGE);
+            std::process::exit(2);
+        }
+    };
+    config::init(cli.config);
+    let public_path = config::site_config().output_dir.as_path();
 
-    for (route, path) in content::get_static_paths() {
-        let path = path.trim_start_matches('/');
-        let path = PathBuf::from(PUBLIC_PATH).join(path);
+    match cli.command.as_deref() {
+        None => {}
+        // Only validate the content without rendering anything.
+        Some("check") => {
+            let errors = content::check::check();
+            if errors.is_empty() {
+                eprintln!(
+                    "Checked {} docs and {} posts, no problems found",
+                    content::DOCS.len(),
+                    content::POSTS.len()
+                );
+                return;
+            }
+            content::error::report(&errors);
+            std::process::exit(1);
+        }
+        Some(command) => {
+            eprintln!("error: unknown command `{command}`");
+            eprintln!("{}", config::USAGE);
+            std::process::exit(2);
+        }
+    }
 
-        eprintln!("Rendering `{}`", path.display());
+    if let Err(errors) = content::load() {
+        content::error::report(&errors);
+        std::process::exit(1);
+    }
 
-        let html = sycamore::render_to_string_await_suspense(|| {
-            view! {
-                Shell {
-                    sycamore_router::StaticRouter(route=route, view=App)
-                }
-            }
+    // Only render the pages whose sources have changed since the last build. The others are
+    // copied from the build cache.
+    let mut cache = build_cache::BuildCache::load(public_path);
+    let all_pages = content::get_static_paths();
+    let total = all_pages.len();
+    let pages = all_pages
+        .into_iter()
+        .map(|(route, path)| {
+            let inputs = content::route_fingerprint(&route);
+            (route, path, inputs)
         })
-        .await;
+        .filter(|(_, path, inputs)| !cache.restore(path, *inputs))
+        .collect::<Vec<_>>();
+    eprintln!(
+        "Rendering {} of {total} pages, the others are unchanged",
+        pages.len()
+    );
 
-        let dir = path.parent().expect("failed to get parent dir");
-        fs::create_dir_all(dir).expect("failed to create parent dir");
-        fs::write(path, format!("<!DOCTYPE html>{html}")).expect("failed to write html file");
-    }
+    // Render the pages on all the available threads. Views are not `Send`, so each thread runs
+    // its own single-threaded runtime with its own reactive root, and renders whole pages.
+    let next_page = AtomicUsize::new(0);
+    let threads = std::thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(pages.len());
+    let rendered = std::thread::scope(|scope| {
+        let handles = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()
+                        .expect("failed to create runtime");
+                    let mut rendered = Vec::new();
+                    while let Some((route, path, inputs)) =
+                        pages.get(next_page.fetch_add(1, Ordering::Relaxed))
+                    {
+                        let outputs =
+                            runtime.block_on(render_page(route.clone(), public_path, path));
+                        rendered.push((path, *inputs, outputs));
+                    }
+                    rendered
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("failed to render pages"))
+            .collect::<Vec<_>>()
+    });
 
     let mut server_components = server_component::SERVER_COMPONENTS.lock().unwrap();
     for (id, html) in server_components.drain() {
-        let path = PathBuf::from(PUBLIC_PATH)
-            .join("server_components")
-            .join(format!("{}.html", id));
+        let hash = server_component::content_hash(&html);
+        let path =
+            public_path.join(server_component::fragment_path(&id, &hash).trim_start_matches('/'));
 
         eprintln!("Rendering server component `{id}` to `{}`", path.display());
 
@@ -60,21 +143,115 @@ async fn main() {
         fs::write(path, html).expect("failed to write html file");
     }
 
+    for (path, inputs, outputs) in rendered {
+        cache.record(path, inputs, outputs);
+    }
+
+    eprintln!("Generating Open Graph images");
+    og_image::generate_og_images(public_path, &mut cache);
+
+    // The server components of the restored pages were not rendered again, so get all of them
+    // from the outputs.
+    let manifest = cache
+        .outputs()
+        .filter_map(server_component::parse_fragment_path)
+        .map(|(id, hash)| (id.to_string(), hash.to_string()))
+        .collect::<server_component::Manifest>();
+    cache.finish();
+
+    eprintln!("Generating server component manifest");
+    let manifest_json = serde_json::to_string(&manifest).expect("failed to serialize manifest");
+    let manifest_dir = public_path.join(server_component::SERVER_COMPONENTS_DIR);
+    fs::create_dir_all(&manifest_dir).expect("failed to create server components dir");
+    fs::write(manifest_dir.join("manifest.json"), &manifest_json)
+        .expect("failed to write server component manifest");
+    // Embed the manifest into every page so that the client does not need to fetch it.
+    for (_, path) in content::get_static_paths() {
+        let path = public_path.join(path.trim_start_matches('/'));
+        let html = fs::read_to_string(&path).expect("failed to read html file");
+        let html = server_component::embed_manifest(&html, &manifest_json);
+        fs::write(path, html).expect("failed to write html file");
+    }
+
     eprintln!("Generating sitemap.xml");
     let sitemap = content::generate_sitemap_xml().expect("failed to generate sitemap");
-    fs::write(PathBuf::from(PUBLIC_PATH).join("sitemap.xml"), sitemap)
-        .expect("failed to write sitemap.xml");
+    fs::write(public_path.join("sitemap.xml"), sitemap).expect("failed to write sitemap.xml");
+
+    eprintln!("Generating feed.xml and atom.xml");
+    for feed in content::feed::feeds() {
+        let dir = public_path.join(feed.dir.trim_start_matches('/'));
+        fs::create_dir_all(&dir).expect("failed to create feed dir");
+        let rss = content::feed::generate_rss_xml(&feed).expect("failed to generate rss feed");
+        fs::write(dir.join("feed.xml"), rss).expect("failed to write feed.xml");
+        let atom = content::feed::generate_atom_xml(&feed).expect("failed to generate atom feed");
+        fs::write(dir.join("atom.xml"), atom).expect("failed to write atom.xml");
+    }
+
+    eprintln!("Generating search index");
+    let search_index = search::generate_search_index();
+    let search_dir = public_path.join(search::SEARCH_DIR);
+    fs::create_dir_all(&search_dir).expect("failed to create search index dir");
+    let docs = serde_json::to_string(&search_index.docs).expect("failed to serialize search docs");
+    fs::write(search_dir.join("docs.json"), docs).expect("failed to write search docs");
+    for (key, shard) in search_index.shards {
+        let shard = serde_json::to_string(&shard).expect("failed to serialize search shard");
+        fs::write(search_dir.join(format!("{key}.json")), shard)
+            .expect("failed to write search shard");
+    }
+
+    eprintln!("Checking links");
+    let dead_links = link_check::check_links(public_path, &manifest);
+    if !dead_links.is_empty() {
+        link_check::report(&dead_links);
+        std::process::exit(1);
+    }
+}
+
+/// Render the page for `route` and write it to `path` inside of `public_path`.
+///
+/// Returns the paths of all the files that the page needs, i.e. the page itself and its server
+/// components.
+#[cfg_ssr]
+async fn render_page(route: Routes, public_path: &std::path::Path, path: &str) -> Vec<String> {
+    let mut outputs = vec![path.to_string()];
+    let path = public_path.join(path.trim_start_matches('/'));
+
+    eprintln!("Rendering `{}`", path.display());
+
+    let html = sycamore::render_to_string_await_suspense(|| {
+        view! {
+            Shell {
+                sycamore_router::StaticRouter(route=route, view=App)
+            }
+        }
+    })
+    .await;
+
+    // Server components are written separately once all the pages have been rendered.
+    let server_components = server_component::SERVER_COMPONENTS.lock().unwrap();
+    outputs.extend(html.split("data-component=\"").skip(1).filter_map(|rest| {
+        let id = &rest[..rest.find('"')?];
+        let hash = server_component::content_hash(server_components.get(id)?);
+        Some(server_component::fragment_path(id, &hash))
+    }));
+    drop(server_components);
+
+    let dir = path.parent().expect("failed to get parent dir");
+    std::fs::create_dir_all(dir).expect("failed to create parent dir");
+    std::fs::write(path, format!("<!DOCTYPE html>{html}")).expect("failed to write html file");
+    outputs
 }
 
 #[cfg_not_ssr]
 fn main() {
     console_error_panic_hook::set_once();
+    prefetch::listen();
 
     sycamore::hydrate_to(
         || {
             view! {
                 Shell {
-                    sycamore_router::Router(integration=sycamore_router::HistoryIntegration::new(), view=App)
+                    sycamore_router::Router(integration=navigation::FragmentNavigation, view=App)
                 }
             }
         },
@@ -0,0 +1,160 @@
+//! Client-side navigation between pages.
+//!
+//! Clicking on a link to another page only updates the route instead of loading the whole page.
+//! Since every page is a server component, this fetches nothing but the fragment of the new page.
+//! Links to anything that is not a page, e.g. the feeds, are still loaded by the browser.
+
+use sycamore::prelude::cfg_not_ssr;
+
+/// The id of the element whose scroll position is kept when navigating, i.e. the sidebar of the
+/// book.
+pub static SIDEBAR_ID: &str = "sidebar";
+
+#[cfg_not_ssr]
+pub use self::client::*;
+
+#[cfg_not_ssr]
+mod client {
+    use std::cell::Cell;
+
+    use sycamore::prelude::*;
+    use sycamore::web::wasm_bindgen::{prelude::Closure, JsCast, JsValue, UnwrapThrowExt};
+    use sycamore_router::{Integration, Route};
+    use web_sys::{Element, HtmlAnchorElement, HtmlTemplateElement, MouseEvent};
+
+    use super::SIDEBAR_ID;
+    use crate::{shell::PAGE_HEAD_ATTR, Routes};
+
+    thread_local! {
+        /// The scroll position of the sidebar when the last navigation started.
+        static SIDEBAR_SCROLL: Cell<Option<i32>> = const { Cell::new(None) };
+    }
+
+    /// A router integration that navigates between pages by fetching their server components.
+    #[derive(Debug, Default)]
+    pub struct FragmentNavigation;
+
+    impl Integration for FragmentNavigation {
+        fn current_pathname(&self) -> String {
+            window().location().pathname().unwrap_throw()
+        }
+
+        fn on_popstate(&self, mut f: Box<dyn FnMut()>) {
+            let closure = Closure::<dyn FnMut()>::new(move || {
+                save_sidebar_scroll();
+                f();
+            });
+            window()
+                .add_event_listener_with_callback("popstate", closure.as_ref().unchecked_ref())
+                .unwrap_throw();
+            closure.forget();
+        }
+
+        fn click_handler(&self) -> Box<dyn Fn(MouseEvent)> {
+            Box::new(on_click)
+        }
+    }
+
+    fn on_click(ev: MouseEvent) {
+        // Let the browser handle clicks that open the link somewhere else, e.g. in a new tab.
+        if ev.default_prevented()
+            || ev.button() != 0
+            || ev.meta_key()
+            || ev.ctrl_key()
+            || ev.shift_key()
+            || ev.alt_key()
+        {
+            return;
+        }
+        let Some(a) = ev
+            .target()
+            .and_then(|target| target.dyn_into::<Element>().ok())
+            .and_then(|element| element.closest("a[href]").ok().flatten())
+        else {
+            return;
+        };
+        let a = a.unchecked_into::<HtmlAnchorElement>();
+        if a.rel() == "external" || !a.target().is_empty() || a.has_attribute("download") {
+            return;
+        }
+
+        let location = window().location();
+        if location.origin().as_ref() != Ok(&a.origin()) {
+            return;
+        }
+        let pathname = a.pathname();
+        // Links to another part of the same page are handled by the browser.
+        if location.pathname().as_ref() == Ok(&pathname) && location.search() == Ok(a.search()) {
+            return;
+        }
+        // Only pages can be navigated to without loading them.
+        if Routes::default().match_path(&pathname) == Routes::NotFound {
+            return;
+        }
+
+        ev.prevent_default();
+        save_sidebar_scroll();
+        window()
+            .history()
+            .unwrap_throw()
+            .push_state_with_url(&JsValue::UNDEFINED, "", Some(&a.href()))
+            .unwrap_throw();
+        sycamore_router::navigate_no_history(&pathname);
+    }
+
+    fn save_sidebar_scroll() {
+        let scroll = document()
+            .get_element_by_id(SIDEBAR_ID)
+            .map(|sidebar| sidebar.scroll_top());
+        SIDEBAR_SCROLL.set(scroll);
+    }
+
+    /// Called once the server component of the new page has been inserted into `container`.
+    /// Updates the head, restores the scroll position of the sidebar and scrolls to the fragment
+    /// of the URL, if any.
+    pub fn fragment_loaded(container: &Element) {
+        update_head(container);
+        if let Some(scroll) = SIDEBAR_SCROLL.take() {
+            if let Some(sidebar) = document().get_element_by_id(SIDEBAR_ID) {
+                sidebar.set_scroll_top(scroll);
+            }
+        }
+        let hash = window().location().hash().unwrap_or_default();
+        if let Some(target) = hash
+            .strip_prefix('#')
+            .filter(|id| !id.is_empty())
+            .and_then(|id| document().get_element_by_id(id))
+        {
+            target.scroll_into_view();
+        }
+    }
+
+    /// Replace the tags in the head that belong to the previous page, e.g. the description and the
+    /// canonical URL, with the ones that came with the server component in `container`.
+    fn update_head(container: &Element) {
+        let Some(template) = container
+            .query_selector(&format!("template[{PAGE_HEAD_ATTR}]"))
+            .unwrap_throw()
+        else {
+            return;
+        };
+        let head = document().head().unwrap_throw();
+        let old = head
+            .query_selector_all(&format!("[{PAGE_HEAD_ATTR}]"))
+            .unwrap_throw();
+        for i in 0..old.length() {
+            if let Some(Ok(tag)) = old.get(i).map(JsCast::dyn_into::<Element>) {
+                tag.remove();
+            }
+        }
+        let template = template.unchecked_into::<HtmlTemplateElement>();
+        head.append_child(&template.content()).unwrap_throw();
+        template.remove();
+    }
+
+    /// Load the current URL from the server instead of only fetching its server component.
+    pub fn full_load() {
+        // Unlike setting the URL, this also loads the page if the URL has a fragment.
+        window().location().reload().unwrap_throw();
+    }
+}
@@ -0,0 +1,304 @@
+//! Generate the Open Graph preview images that are shown when a page is shared.
+//!
+//! Every book page and blog post gets a PNG card with its title, the part of the site that it
+//! belongs to and the Sycamore logo.
+
+use std::{fs, path::Path, sync::LazyLock};
+
+use ab_glyph::{Font, FontRef, OutlineCurve, PxScale, ScaleFont};
+use resvg::{
+    tiny_skia::{ColorU8, FillRule, Paint, PathBuilder, Pixmap, Rect, Transform},
+    usvg,
+};
+
+use crate::{
+    build_cache::{self, BuildCache},
+    content::{BOOK_INDEX, DEFAULT_VERSION, POSTS},
+};
+
+/// The directory that the images are written to.
+pub static OG_DIR: &str = "og";
+
+/// The size of the images. This is the size that is recommended by most social networks.
+pub const WIDTH: usize = 1200;
+pub const HEIGHT: usize = 630;
+
+const MARGIN: f32 = 80.0;
+
+const BACKGROUND: ColorU8 = ColorU8::from_rgba(0x11, 0x18, 0x27, 0xff);
+const TITLE_COLOR: ColorU8 = ColorU8::from_rgba(0xf9, 0xfa, 0xfb, 0xff);
+const LABEL_COLOR: ColorU8 = ColorU8::from_rgba(0xf3, 0x96, 0x36, 0xff);
+const ACCENT_COLOR: ColorU8 = ColorU8::from_rgba(0xf8, 0xb9, 0x4c, 0xff);
+
+static LOGO: LazyLock<usvg::Tree> = LazyLock::new(|| {
+    usvg::Tree::from_str(
+        include_str!("../assets/logo.svg"),
+        &usvg::Options::default(),
+    )
+    .expect("invalid logo")
+});
+
+static FONT: LazyLock<FontRef<'static>> = LazyLock::new(|| {
+    FontRef::try_from_slice(include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf"))
+        .expect("invalid font")
+});
+
+/// Get the path of the preview image of the page at `url`, e.g. `/book/next/introduction` becomes
+/// `/og/book/next/introduction.png`.
+pub fn image_path(url: &str) -> String {
+    format!("/{OG_DIR}{url}.png")
+}
+
+fn paint(color: ColorU8) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(color.red(), color.green(), color.blue(), color.alpha());
+    paint
+}
+
+/// The width of `text` in pixels when it is drawn at `size`.
+fn text_width(text: &str, size: f32) -> f32 {
+    let font = FONT.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            width += font.kern(previous, id);
+        }
+        width += font.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+/// Draw `text` at `size` with its baseline starting at `x` and `y`.
+fn draw_text(pixmap: &mut Pixmap, text: &str, x: f32, y: f32, size: f32, color: ColorU8) {
+    let font = FONT.as_scaled(PxScale::from(size));
+    let (sx, sy) = (font.h_scale_factor(), font.v_scale_factor());
+    let mut path = PathBuilder::new();
+    let mut x = x;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            x += font.kern(previous, id);
+        }
+        // Glyphs are outlined in font units with the y axis pointing up.
+        let point = |p: ab_glyph::Point| (x + p.x * sx, y - p.y * sy);
+        let mut end = None;
+        for curve in FONT.outline(id).map(|outline| outline.curves).unwrap_or_default() {
+            let (start, to) = match curve {
+                OutlineCurve::Line(a, b) => (a, b),
+                OutlineCurve::Quad(a, _, b) => (a, b),
+                OutlineCurve::Cubic(a, _, _, b) => (a, b),
+            };
+            // Start a new contour if the curve does not continue the previous one.
+            if end != Some(start) {
+                let (x, y) = point(start);
+                path.move_to(x, y);
+            }
+            let (x1, y1) = point(to);
+            match curve {
+                OutlineCurve::Line(..) => path.line_to(x1, y1),
+                OutlineCurve::Quad(_, c, _) => {
+                    let (cx, cy) = point(c);
+                    path.quad_to(cx, cy, x1, y1);
+                }
+                OutlineCurve::Cubic(_, c1, c2, _) => {
+                    let ((c1x, c1y), (c2x, c2y)) = (point(c1), point(c2));
+                    path.cubic_to(c1x, c1y, c2x, c2y, x1, y1);
+                }
+            }
+            end = Some(to);
+        }
+        x += font.h_advance(id);
+        previous = Some(id);
+    }
+
+    let Some(path) = path.finish() else {
+        return;
+    };
+    pixmap.fill_path(
+        &path,
+        &paint(color),
+        FillRule::Winding,
+        Transform::identity(),
+        None,
+    );
+}
+
+/// Split `text` into lines that are at most `max_width` pixels wide at `size`, breaking at spaces
+/// where possible. If the text does not fit in `max_lines`, the last line ends with an ellipsis.
+fn wrap(text: &str, size: f32, max_width: f32, max_lines: usize) -> Vec<String> {
+    let fits = |line: &str| text_width(line, size) <= max_width;
+    let mut lines = Vec::<String>::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if current.is_empty() {
+            word.to_string()
+        } else {
+            format!("{current} {word}")
+        };
+        if fits(&candidate) {
+            current = candidate;
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        // Split words that are longer than a line by themselves.
+        for c in word.chars() {
+            if !current.is_empty() && !fits(&format!("{current}{c}")) {
+                lines.push(std::mem::take(&mut current));
+            }
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = lines.last_mut().unwrap();
+        while !last.is_empty() && !fits(&format!("{last}…")) {
+            last.pop();
+        }
+        last.truncate(last.trim_end().len());
+        last.push('…');
+    }
+    lines
+}
+
+/// Render a preview card with a `label` above the `title`.
+pub fn render_card(label: &str, title: &str) -> Vec<u8> {
+    let mut pixmap = Pixmap::new(WIDTH as u32, HEIGHT as u32).expect("invalid image size");
+    let background = Rect::from_xywh(0.0, 0.0, WIDTH as f32, HEIGHT as f32).unwrap();
+    pixmap.fill_rect(background, &paint(BACKGROUND), Transform::identity(), None);
+    let max_width = WIDTH as f32 - 2.0 * MARGIN;
+
+    // Logo and name in the top left corner.
+    let logo_size = 112.0;
+    let logo_top = 64.0;
+    let scale = logo_size / LOGO.size().width();
+    resvg::render(
+        &LOGO,
+        Transform::from_scale(scale, scale).post_translate(MARGIN, logo_top),
+        &mut pixmap.as_mut(),
+    );
+    let name_size = 64.0;
+    // Roughly center the capital letters vertically next to the logo.
+    let name_baseline = logo_top + logo_size / 2.0 + name_size * 0.35;
+    draw_text(
+        &mut pixmap,
+        "Sycamore",
+        MARGIN + logo_size + 32.0,
+        name_baseline,
+        name_size,
+        TITLE_COLOR,
+    );
+
+    let label_size = 36.0;
+    let label = wrap(label, label_size, max_width, 1).concat();
+    draw_text(&mut pixmap, &label, MARGIN, 270.0, label_size, LABEL_COLOR);
+
+    // Use a larger font for short titles.
+    let (size, lines) = [80.0, 64.0, 52.0]
+        .into_iter()
+        .map(|size| (size, wrap(title, size, max_width, 3)))
+        .find(|(size, lines)| lines.len() <= 2 || *size <= 52.0)
+        .unwrap();
+    for (i, line) in lines.iter().enumerate() {
+        let baseline = 320.0 + size + i as f32 * size * 1.2;
+        draw_text(&mut pixmap, line, MARGIN, baseline, size, TITLE_COLOR);
+    }
+
+    // Accent bar along the bottom edge.
+    let bar = Rect::from_xywh(0.0, HEIGHT as f32 - 16.0, WIDTH as f32, 16.0).unwrap();
+    pixmap.fill_rect(bar, &paint(ACCENT_COLOR), Transform::identity(), None);
+
+    pixmap.encode_png().expect("failed to encode og image")
+}
+
+/// Generate the preview images for all the book pages and blog posts. Images whose label and
+/// title have not changed since the last build are restored from the `cache`.
+pub fn generate_og_images(public_path: &Path, cache: &mut BuildCache) {
+    let mut cards = Vec::new();
+    for (version, index) in BOOK_INDEX.iter() {
+        // Only mention the version if it is not the one that is shown by default.
+        let book = if version == &*DEFAULT_VERSION {
+            "Book".to_string()
+        } else {
+            format!("Book · {version}")
+        };
+        for section in &index.sections {
+            cards.push((section.path.href(true), book.clone(), section.title.clone()));
+            for item in &section.subsections {
+                let label = format!("{book} · {}", section.title);
+                cards.push((item.path.href(true), label, item.title.clone()));
+            }
+        }
+    }
+    // Unlisted posts are not meant to be shared.
+    for (id, post) in POSTS.iter().filter(|(_, post)| !post.front_matter.unlisted) {
+        let label = format!("Blog · {}", post.front_matter.date);
+        cards.push((format!("/post/{id}"), label, post.front_matter.title.clone()));
+    }
+
+    for (url, label, title) in cards {
+        let image_path = image_path(&url);
+        let inputs = build_cache::fingerprint((&label, &title));
+        if cache.restore(&image_path, inputs) {
+            continue;
+        }
+        let path = public_path.join(image_path.trim_start_matches('/'));
+        let dir = path.parent().expect("failed to get parent dir");
+        fs::create_dir_all(dir).expect("failed to create parent dir");
+        fs::write(path, render_card(&label, &title)).expect("failed to write og image");
+        cache.record(&image_path, inputs, vec![image_path.clone()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_breaks_at_spaces() {
+        let lines = wrap("Announcing Sycamore v0.9.0", 80.0, 900.0, 3);
+        assert_eq!(lines, ["Announcing Sycamore", "v0.9.0"]);
+        assert!(lines.iter().all(|line| text_width(line, 80.0) <= 900.0));
+    }
+
+    #[test]
+    fn wrap_splits_long_words() {
+        let lines = wrap(&"a".repeat(100), 52.0, 1040.0, 3);
+        assert!(lines.len() > 1);
+        assert_eq!(lines.concat(), "a".repeat(100));
+        assert!(lines.iter().all(|line| text_width(line, 52.0) <= 1040.0));
+    }
+
+    #[test]
+    fn wrap_ends_truncated_text_with_ellipsis() {
+        let lines = wrap(&"word ".repeat(100), 52.0, 1040.0, 3);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[2].ends_with('…'));
+        assert!(text_width(&lines[2], 52.0) <= 1040.0);
+    }
+
+    #[test]
+    fn render_card_encodes_png() {
+        let png = render_card("Book · Introduction", "Installation");
+        let pixmap = Pixmap::decode_png(&png).expect("invalid png");
+        assert_eq!(
+            (pixmap.width() as usize, pixmap.height() as usize),
+            (WIDTH, HEIGHT)
+        );
+        // The accent bar is drawn along the bottom edge.
+        let pixel = pixmap.pixel(0, HEIGHT as u32 - 1).unwrap();
+        assert_eq!(
+            (pixel.red(), pixel.green(), pixel.blue()),
+            (ACCENT_COLOR.red(), ACCENT_COLOR.green(), ACCENT_COLOR.blue())
+        );
+    }
+}
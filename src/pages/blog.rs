@@ -0,0 +1,359 @@
+use sycamore::prelude::*;
+
+use crate::{server_component::ServerOnly, set_title, shell::server_component_id};
+
+/// A page of the list of all the blog posts, starting from 1.
+#[component(inline_props)]
+pub fn Blog(page: u32) -> View {
+    if page <= 1 {
+        set_title("Blog");
+    } else {
+        set_title(format!("Blog - Page {page}"));
+    }
+    view! {
+        ServerOnly(id=server_component_id()) {
+            BlogBody(page=page)
+        }
+    }
+}
+
+/// The list of the blog posts with the tag with the given slug.
+#[component(inline_props)]
+pub fn BlogTag(tag: String) -> View {
+    view! {
+        ServerOnly(id=server_component_id()) {
+            BlogTagBody(tag=tag)
+        }
+    }
+}
+
+/// The list of the blog posts by the author with the given GitHub handle.
+#[component(inline_props)]
+pub fn BlogAuthor(handle: String) -> View {
+    view! {
+        ServerOnly(id=server_component_id()) {
+            BlogAuthorBody(handle=handle)
+        }
+    }
+}
+
+#[cfg_not_ssr]
+#[component(inline_props)]
+fn BlogBody(page: u32) -> View {
+    let _ = page;
+    unreachable!()
+}
+
+#[cfg_ssr]
+#[component(inline_props)]
+fn BlogBody(page: u32) -> View {
+    use crate::{
+        config::site_config,
+        content::{blog_page_count, blog_page_href, sorted_posts, POSTS_PER_PAGE},
+        shell::{set_page_meta, set_prev_next, PageMeta},
+    };
+
+    let page_count = blog_page_count();
+    let posts = sorted_posts()
+        .into_iter()
+        .skip((page as usize - 1) * POSTS_PER_PAGE)
+        .take(POSTS_PER_PAGE)
+        .map(|(id, post)| view! { PostSummary(id=id.clone(), post=post.clone()) })
+        .collect::<Vec<_>>();
+
+    let base_url = &site_config().base_url;
+    set_page_meta(PageMeta {
+        description: Some("News and updates about Sycamore".to_string()),
+        canonical_url: Some(format!("{base_url}{}", blog_page_href(page))),
+        ..Default::default()
+    });
+    let newer = (page > 1).then(|| blog_page_href(page - 1));
+    let older = (page < page_count).then(|| blog_page_href(page + 1));
+    set_prev_next(newer.clone(), older.clone());
+
+    let newer = newer.map(|href| {
+        view! {
+            a(href=href, class="hover:underline") { i(class="bi bi-arrow-left") " Newer posts" }
+        }
+    });
+    let older = older.map(|href| {
+        view! {
+            a(href=href, class="hover:underline") { "Older posts " i(class="bi bi-arrow-right") }
+        }
+    });
+    let pages = (1..=page_count)
+        .map(|n| {
+            let class = if n == page {
+                "px-2 rounded bg-orange-400 dark:bg-orange-500"
+            } else {
+                "px-2 rounded hover:underline"
+            };
+            view! {
+                a(href=blog_page_href(n), class=class) { (n.to_string()) }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    view! {
+        div(class="flex flex-col container px-2 md:px-20 mx-auto max-w-[80ch] pt-10 pb-10") {
+            h1(class="text-4xl font-bold") { "Blog" }
+            TagCloud(current=None)
+            (posts)
+            nav(class="flex flex-row items-center gap-4 mt-10", data-prefetch=crate::prefetch::VISIBLE) {
+                (newer)
+                div(class="flex flex-row gap-1 mx-auto") {
+                    (pages)
+                }
+                (older)
+            }
+        }
+    }
+}
+
+#[cfg_not_ssr]
+#[component(inline_props)]
+fn BlogTagBody(tag: String) -> View {
+    let _ = tag;
+    unreachable!()
+}
+
+#[cfg_ssr]
+#[component(inline_props)]
+fn BlogTagBody(tag: String) -> View {
+    use crate::{
+        config::site_config,
+        content::{feed::tag_feed_dir, POSTS, TAGS},
+        server_component::ServerTitle,
+        shell::{set_page_meta, PageMeta},
+    };
+
+    let info = TAGS.get(&tag).expect("tag not found");
+    let posts = info
+        .posts
+        .iter()
+        .map(|id| {
+            let post = POSTS.get(id).expect("post not found").clone();
+            view! { PostSummary(id=id.clone(), post=post) }
+        })
+        .collect::<Vec<_>>();
+
+    let base_url = &site_config().base_url;
+    set_page_meta(PageMeta {
+        description: Some(format!("Posts about {} on the Sycamore blog", info.name)),
+        canonical_url: Some(format!("{base_url}/blog/tag/{tag}")),
+        ..Default::default()
+    });
+    let feed_dir = tag_feed_dir(&tag);
+    let rss_href = format!("{feed_dir}feed.xml");
+    let atom_href = format!("{feed_dir}atom.xml");
+    let title = format!("Posts tagged “{}”", info.name);
+    let heading = title.clone();
+
+    view! {
+        ServerTitle(title=title)
+        div(class="flex flex-col container px-2 md:px-20 mx-auto max-w-[80ch] pt-10 pb-10") {
+            h1(class="text-4xl font-bold") { (heading) }
+            p(class="text-sm mt-2") {
+                a(href=rss_href, class="underline") { i(class="bi bi-rss") " RSS" }
+                " · "
+                a(href=atom_href, class="underline") { "Atom" }
+            }
+            TagCloud(current=Some(tag))
+            (posts)
+            a(href="/blog", class="mt-10 hover:underline") { i(class="bi bi-arrow-left") " All posts" }
+        }
+    }
+}
+
+#[cfg_not_ssr]
+#[component(inline_props)]
+fn BlogAuthorBody(handle: String) -> View {
+    let _ = handle;
+    unreachable!()
+}
+
+#[cfg_ssr]
+#[component(inline_props)]
+fn BlogAuthorBody(handle: String) -> View {
+    use crate::{
+        config::site_config,
+        content::{author_posts, AUTHORS, POSTS},
+        server_component::ServerTitle,
+        shell::{set_page_meta, PageMeta},
+    };
+
+    let author = AUTHORS.get(&handle).expect("author not found");
+    let posts = author_posts(&handle)
+        .into_iter()
+        .map(|id| {
+            let post = POSTS.get(id).expect("post not found").clone();
+            view! { PostSummary(id=id.clone(), post=post) }
+        })
+        .collect::<Vec<_>>();
+
+    let base_url = &site_config().base_url;
+    set_page_meta(PageMeta {
+        description: Some(format!("Posts by {} on the Sycamore blog", author.name)),
+        canonical_url: Some(format!("{base_url}{}", author.href())),
+        ..Default::default()
+    });
+    let title = format!("Posts by {}", author.name);
+    let name = author.name.clone();
+    let avatar_url = author.avatar_url();
+    let github_url = author.github_url();
+    let github_handle = format!("@{handle}");
+
+    view! {
+        ServerTitle(title=title)
+        div(class="flex flex-col container px-2 md:px-20 mx-auto max-w-[80ch] pt-10 pb-10") {
+            div(class="flex flex-row items-center gap-4") {
+                img(src=avatar_url, alt="", class="rounded-full w-16 h-16")
+                div {
+                    h1(class="text-4xl font-bold") { (name) }
+                    a(href=github_url, class="text-sm underline") { i(class="bi bi-github") " " (github_handle) }
+                }
+            }
+            (posts)
+            a(href="/blog", class="mt-10 hover:underline") { i(class="bi bi-arrow-left") " All posts" }
+        }
+    }
+}
+
+/// The avatars and names of the authors of a post, each linking to the list of their posts.
+#[cfg_ssr]
+#[component(inline_props)]
+pub fn Byline(authors: Vec<crate::content::Author>) -> View {
+    if authors.is_empty() {
+        return view! {};
+    }
+    let authors = authors
+        .into_iter()
+        .map(|author| {
+            let href = author.href();
+            let avatar_url = author.avatar_url();
+            let content = view! {
+                img(src=avatar_url, alt="", class="rounded-full w-6 h-6 !my-0", loading="lazy")
+                span(class="text-sm font-semibold") { (author.name) }
+            };
+            // Authors of only unlisted posts do not have a page.
+            if crate::content::author_posts(&author.handle).is_empty() {
+                view! {
+                    span(class="flex flex-row items-center gap-2") { (content) }
+                }
+            } else {
+                view! {
+                    a(href=href, class="flex flex-row items-center gap-2 no-underline hover:underline") {
+                        (content)
+                    }
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    view! {
+        div(class="not-prose flex flex-row flex-wrap gap-4 mt-2") {
+            (authors)
+        }
+    }
+}
+
+/// All the tags, each linking to the list of posts with that tag. Tags with more posts are shown
+/// larger.
+#[cfg_ssr]
+#[component(inline_props)]
+fn TagCloud(#[prop(!optional)] current: Option<String>) -> View {
+    let tags = &*crate::content::TAGS;
+    let max = tags.values().map(|tag| tag.posts.len()).max().unwrap_or(1);
+    let tags = tags
+        .iter()
+        .map(|(slug, tag)| {
+            static SIZES: &[&str] = &["text-sm", "text-base", "text-lg", "text-xl"];
+            // Scale linearly from the smallest size for one post to the largest for `max` posts.
+            let size = SIZES[(tag.posts.len() - 1) * (SIZES.len() - 1) / (max - 1).max(1)];
+            let class = if current.as_deref() == Some(slug.as_str()) {
+                format!("{size} px-2 rounded-full bg-orange-400 dark:bg-orange-500")
+            } else {
+                format!("{size} px-2 rounded-full hover:underline")
+            };
+            let label = format!("{} ({})", tag.name, tag.posts.len());
+            view! {
+                a(href=format!("/blog/tag/{slug}"), class=class) { (label) }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    view! {
+        div(class="flex flex-row flex-wrap items-baseline gap-2 mt-4") {
+            (tags)
+        }
+    }
+}
+
+/// Chips for the tags of a post, each linking to the list of posts with that tag.
+#[cfg_ssr]
+#[component(inline_props)]
+pub fn TagChips(tags: Vec<String>) -> View {
+    let chips = tags
+        .into_iter()
+        .map(|tag| {
+            let slug = crate::content::tag_slug(&tag);
+            // Tags of unlisted posts only have a page if a listed post has them as well.
+            if crate::content::TAGS.contains_key(&slug) {
+                view! {
+                    a(href=format!("/blog/tag/{slug}"), class="text-xs px-2 py-0.5 rounded-full bg-orange-100 text-orange-900 hover:bg-orange-200 dark:bg-orange-950 dark:text-orange-200 dark:hover:bg-orange-900 no-underline") {
+                        (tag)
+                    }
+                }
+            } else {
+                view! {
+                    span(class="text-xs px-2 py-0.5 rounded-full bg-orange-100 text-orange-900 dark:bg-orange-950 dark:text-orange-200") {
+                        (tag)
+                    }
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    view! {
+        div(class="flex flex-row flex-wrap gap-2") {
+            (chips)
+        }
+    }
+}
+
+/// The date, title, description, reading time, authors and tags of a post.
+#[cfg_ssr]
+#[component(inline_props)]
+pub fn PostSummary(id: String, post: mdsycx::ParseRes<crate::content::PostFrontmatter>) -> View {
+    let reading_time = crate::content::reading_time(&post.body);
+    let authors = crate::content::post_authors(&post.front_matter)
+        .into_iter()
+        .map(|author| author.name)
+        .collect::<Vec<_>>();
+    let mut byline = if authors.is_empty() {
+        String::new()
+    } else {
+        format!(" · by {}", authors.join(", "))
+    };
+    if post.front_matter.draft {
+        byline.push_str(" · Draft");
+    }
+    view! {
+        div(class="mt-5") {
+            a(href=format!("/post/{id}")) {
+                p(class="text-xs") {
+                    (format!("{} · {reading_time} min read{byline}", post.front_matter.date))
+                }
+                p(class="text-2xl font-semibold") {
+                    (post.front_matter.title.clone())
+                }
+                p(class="text-gray-800 dark:text-gray-400") {
+                    (post.front_matter.description.clone())
+                }
+            }
+            div(class="mt-1") {
+                TagChips(tags=post.front_matter.tags.clone())
+            }
+        }
+    }
+}
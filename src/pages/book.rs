@@ -1,17 +1,14 @@
 use sycamore::prelude::*;
 
-use crate::server_component::ServerOnly;
+use crate::{server_component::ServerOnly, shell::server_component_id, Version};
 
+/// A page of the book. If `version` is `None`, the default version is shown.
 #[component(inline_props)]
-pub fn Book(section: String, doc: Option<String>) -> View {
-    let id = match &doc {
-        Some(doc) => format!("Book_{section}_{doc}"),
-        None => format!("Book_{section}"),
-    };
+pub fn Book(version: Option<Version>, section: String, doc: Option<String>) -> View {
     view! {
         sycamore::web::Suspense {
-            ServerOnly(id=id, on_load=|| { crate::utils::prism_highlight_all(); }) {
-                BookBody(section=section, doc=doc)
+            ServerOnly(id=server_component_id()) {
+                BookBody(version=version, section=section, doc=doc)
             }
         }
     }
@@ -19,7 +16,12 @@ pub fn Book(section: String, doc: Option<String>) -> View {
 
 #[cfg_not_ssr]
 #[component(inline_props)]
-fn BookBody(section: String, #[prop(!optional)] doc: Option<String>) -> View {
+fn BookBody(
+    #[prop(!optional)] version: Option<Version>,
+    section: String,
+    #[prop(!optional)] doc: Option<String>,
+) -> View {
+    let _ = version;
     let _ = section;
     let _ = doc;
     unreachable!()
@@ -27,26 +29,89 @@ fn BookBody(section: String, #[prop(!optional)] doc: Option<String>) -> View {
 
 #[cfg_ssr]
 #[component(inline_props)]
-fn BookBody(section: String, #[prop(!optional)] doc: Option<String>) -> View {
-    use crate::server_component::ServerTitle;
-
-    let parsed = crate::content::DOCS
-        .get(&crate::content::DocPage::new(section.clone(), doc.clone()))
-        .expect("doc not found")
-        .clone();
-
-    let github_edit_link = match &doc {
-        Some(doc) => format!(
-            "https://github.com/sycamore-rs/sycamore/edit/main/docs/next/{section}/{doc}.md"
-        ),
-        None => format!("https://github.com/sycamore-rs/sycamore/edit/main/docs/next/{section}.md"),
+fn BookBody(
+    #[prop(!optional)] version: Option<Version>,
+    section: String,
+    #[prop(!optional)] doc: Option<String>,
+) -> View {
+    use crate::{
+        config::site_config,
+        content::{
+            first_paragraph, versioned_book_links, DocPage, BOOK_INDEX, DEFAULT_VERSION, DOCS,
+        },
+        og_image,
+        server_component::ServerTitle,
+        shell::{set_page_meta, PageImage, PageMeta},
     };
 
+    let page = DocPage::new(
+        version.clone().unwrap_or_else(|| DEFAULT_VERSION.clone()),
+        section.clone(),
+        doc.clone(),
+    );
+    let mut parsed = DOCS.get(&page).expect("doc not found").clone();
+    // Keep links inside of versioned pages pointing to the same version.
+    if let Some(version) = &version {
+        parsed.body = versioned_book_links(&parsed.body, version);
+    }
+
+    let base_url = &site_config().base_url;
+    // Pages of the default version are canonically served without the version in the URL.
+    let canonical_path = page.href(*page.version() != *DEFAULT_VERSION);
+    set_page_meta(PageMeta {
+        description: parsed
+            .front_matter
+            .description
+            .clone()
+            .or_else(|| first_paragraph(&parsed.body)),
+        canonical_url: Some(format!("{base_url}{canonical_path}")),
+        // The images are only generated once for each version.
+        image: Some(PageImage {
+            url: format!("{base_url}{}", og_image::image_path(&page.href(true))),
+            width: og_image::WIDTH,
+            height: og_image::HEIGHT,
+        }),
+        published_time: None,
+        authors: Vec::new(),
+    });
+
+    let github_edit_link = format!(
+        "{}/edit/main/docs/{}.md",
+        site_config().repository_url(),
+        page.file_path()
+    );
+
+    let versioned = version.is_some();
+    let (prev, next) = BOOK_INDEX[page.version()].neighbours(&page);
+    crate::shell::set_prev_next(
+        prev.map(|item| item.path.href(versioned)),
+        next.map(|item| item.path.href(versioned)),
+    );
+    let prev = prev.map(|item| {
+        view! {
+            a(class="flex-1 p-3 rounded border-2 border-gray-200 dark:border-gray-800 hover:border-orange-700 dark:hover:border-orange-500 transition-colors", href=item.path.href(versioned)) {
+                p(class="text-xs text-gray-700 dark:text-gray-300") { "← Previous" }
+                p(class="font-semibold") { (item.title.clone()) }
+            }
+        }
+    });
+    let next = next.map(|item| {
+        view! {
+            a(class="flex-1 p-3 rounded border-2 border-gray-200 dark:border-gray-800 hover:border-orange-700 dark:hover:border-orange-500 transition-colors text-right", href=item.path.href(versioned)) {
+                p(class="text-xs text-gray-700 dark:text-gray-300") { "Next →" }
+                p(class="font-semibold") { (item.title.clone()) }
+            }
+        }
+    });
+
+    let prev_missing = prev.is_none();
+
+    let index_page = page.clone();
     view! {
         ServerTitle(title=parsed.front_matter.title)
         div(class="flex flex-row gap-0 sm:gap-4 w-full justify-center") {
-            div(class="flex-none w-44 pt-8 pb-5 px-2 space-y-2 text-sm sticky top-12 max-h-[calc(100vh-3rem)] overflow-y-auto block -ml-44 sm:ml-0") {
-                BookIndex(section=section, doc=doc)
+            div(id=crate::navigation::SIDEBAR_ID, data-prefetch=crate::prefetch::HOVER, class="flex-none w-44 pt-8 pb-5 px-2 space-y-2 text-sm sticky top-12 max-h-[calc(100vh-3rem)] overflow-y-auto block -ml-44 sm:ml-0") {
+                BookIndex(page=index_page, versioned=versioned)
             }
             div(class="grow-0 min-w-0 px-2 pt-5 pb-10 prose md:w-[80ch] dark:prose-invert") {
                 mdsycx::MDSycX(body=parsed.body)
@@ -57,28 +122,76 @@ fn BookBody(section: String, #[prop(!optional)] doc: Option<String>) -> View {
                         "Edit this page on GitHub"
                     }
                 }
+
+                nav(class="not-prose mt-6 flex flex-row gap-4", data-prefetch=crate::prefetch::VISIBLE) {
+                    (prev)
+                    // Keep the next card on the right even if there is no previous page.
+                    (if prev_missing { view! { div(class="flex-1") } } else { view! {} })
+                    (next)
+                }
             }
             crate::utils::HeadingsOutline(headings=parsed.headings) {
-                div(class="mt-4") {
-                    p(class="uppercase text-xs") { "Versions" }
-                    ul(class="font-semibold mt-1") {
-                        li {
-                            "v0.9" span(class="font-normal") { " (current)" }
-                        }
-                        li {
-                            a(class="hover:text-orange-700 dark:hover:text-orange-500", href="https://sycamore-rs.netlify.app/docs/v0.8/getting_started/installation") {
-                                "v0.8"
-                                i(class="bi bi-box-arrow-up-right ml-2")
-                            }
-                        }
-                        li {
-                            a(class="hover:text-orange-700 dark:hover:text-orange-500", href="https://sycamore-rs.netlify.app/docs/v0.7/getting_started/installation") {
-                                "v0.7"
-                                i(class="bi bi-box-arrow-up-right ml-2")
-                            }
-                        }
+                VersionSwitcher(page=page)
+            }
+        }
+    }
+}
+
+/// Links to the same page in all the other versions of the book.
+#[cfg_ssr]
+#[component(inline_props)]
+fn VersionSwitcher(page: crate::content::DocPage) -> View {
+    use crate::content::{DocPage, BOOK_INDEX, DOCS, VERSIONS};
+
+    // The newest version that has been released.
+    let latest = VERSIONS.iter().find(|version| !version.is_unreleased());
+
+    let versions = VERSIONS
+        .iter()
+        .filter_map(|version| {
+            let label = if version.is_unreleased() {
+                " (unreleased)"
+            } else if Some(version) == latest {
+                " (latest)"
+            } else {
+                ""
+            };
+            let name = version.to_string();
+            if version == page.version() {
+                return Some(view! {
+                    li {
+                        (name) span(class="font-normal") { (label) }
+                    }
+                });
+            }
+
+            // Link to the same page in the other version if it exists, otherwise to the start of
+            // the book. Versions without any sections have no pages to link to.
+            let same_page = DocPage::new(
+                version.clone(),
+                page.section().to_string(),
+                page.subsection().map(ToString::to_string),
+            );
+            let href = if DOCS.contains_key(&same_page) {
+                same_page.href(true)
+            } else {
+                BOOK_INDEX[version].sections.first()?.path.href(true)
+            };
+            Some(view! {
+                li {
+                    a(class="hover:text-orange-700 dark:hover:text-orange-500", href=href) {
+                        (name) span(class="font-normal") { (label) }
                     }
                 }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    view! {
+        div(class="mt-4") {
+            p(class="uppercase text-xs") { "Versions" }
+            ul(class="font-semibold mt-1") {
+                (versions)
             }
         }
     }
@@ -86,15 +199,12 @@ fn BookBody(section: String, #[prop(!optional)] doc: Option<String>) -> View {
 
 #[cfg_ssr]
 #[component(inline_props)]
-fn BookIndex(section: String, #[prop(!optional)] doc: Option<String>) -> View {
+fn BookIndex(page: crate::content::DocPage, versioned: bool) -> View {
     use crate::content::BOOK_INDEX;
 
-    let current_href = match &doc {
-        Some(doc) => format!("/book/{}/{}", section, doc),
-        None => format!("/book/{}", section),
-    };
+    let current_href = page.href(versioned);
 
-    BOOK_INDEX
+    BOOK_INDEX[page.version()]
         .clone()
         .sections
         .into_iter()
@@ -103,11 +213,7 @@ fn BookIndex(section: String, #[prop(!optional)] doc: Option<String>) -> View {
                 .subsections
                 .into_iter()
                 .map(|item| {
-                    let href = format!(
-                        "/book/{}/{}",
-                        item.path.section(),
-                        item.path.subsection().unwrap()
-                    );
+                    let href = item.path.href(versioned);
                     let class = if href == current_href {
                         "text-orange-700 dark:text-orange-500"
                     } else {
@@ -120,7 +226,7 @@ fn BookIndex(section: String, #[prop(!optional)] doc: Option<String>) -> View {
                     }
                 })
                 .collect::<Vec<_>>();
-            let href = format!("/book/{}", section.path.section());
+            let href = section.path.href(versioned);
             let class = if href == current_href {
                 "font-semibold text-orange-700 dark:text-orange-500"
             } else {
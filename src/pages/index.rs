@@ -1,12 +1,12 @@
 use sycamore::prelude::*;
 
-use crate::{server_component::ServerOnly, set_title};
+use crate::{server_component::ServerOnly, set_title, shell::server_component_id};
 
 #[component]
 pub fn Index() -> View {
     set_title("Sycamore");
     view! {
-        ServerOnly(id="IndexBody".to_string(), on_load=move || { crate::utils::prism_highlight_all(); }) {
+        ServerOnly(id=server_component_id()) {
             IndexBody {}
         }
     }
@@ -18,10 +18,26 @@ fn IndexBody() -> View {
     unreachable!()
 }
 
+#[cfg_ssr]
+static EXAMPLE: &str = r#"use sycamore::prelude::*;
+
+#[component]
+fn Counter(initial: i32) -> View {
+    let mut value = create_signal(initial);
+
+    view! {
+        button(on:click=move |_| value += 1) {
+            "Count: " (value)
+        }
+    }
+}"#;
+
 #[cfg_ssr]
 #[component]
 fn IndexBody() -> View {
     let latest_release = crate::api_stats::get_latest_release();
+    let discord = crate::config::site_config().discord.clone();
+    let example = crate::content::highlight::highlight(EXAMPLE, "rust");
     view! {
         div(class="flex flex-col container px-2 md:px-20 mx-auto pb-10") {
             div(class="mt-10 md:mt-20 flex flex-col md:flex-row gap-10 items-center justify-between") {
@@ -39,7 +55,7 @@ fn IndexBody() -> View {
                         a(class="block px-5 py-1.5 min-w-40 text-center bg-orange-400 dark:bg-orange-500 rounded-full hover:bg-orange-500 dark:hover:bg-orange-400 transition-colors", href="/book/introduction", data-umami-event="Read book") {
                             "Read the Book"
                         }
-                        a(class="block px-5 py-1.5 min-w-40 text-center text-white bg-gray-800 rounded-full hover:bg-gray-900 transition-colors", href="https://discord.gg/vDwFUmm6mU", data-umami-event="Join discord") {
+                        a(class="block px-5 py-1.5 min-w-40 text-center text-white bg-gray-800 rounded-full hover:bg-gray-900 transition-colors", href=discord, data-umami-event="Join discord") {
                             "Join the Discord"
                         }
                     }
@@ -52,18 +68,7 @@ fn IndexBody() -> View {
                 div(class="flex-grow w-full md:w-auto") {
                     pre(class="bg-gray-800 rounded-lg text-white text-xs sm:text-sm md:text-base overflow-x-hidden w-full md:max-w-[550px] shadow-lg !mx-auto language-rust") {
                         code(class="language-rust") {
-                            r#"use sycamore::prelude::*;
-
-#[component]
-fn Counter(initial: i32) -> View {
-    let mut value = create_signal(initial);
-
-    view! {
-        button(on:click=move |_| value += 1) {
-            "Count: " (value)
-        }
-    }
-}"#
+                            mdsycx::MDSycX(body=example)
                         }
                     }
                 }
@@ -170,6 +175,20 @@ fn FeatureCard(icon: &'static str, title: &'static str, children: Children) -> V
     }
 }
 
+/// The number of contributors that are shown before the rest are collapsed.
+#[cfg_ssr]
+const SHOWN_CONTRIBUTORS: usize = 48;
+
+#[cfg_ssr]
+#[component(inline_props)]
+fn ContributorAvatar(contributor: crate::api_stats::Contributor) -> View {
+    view! {
+        a(href=contributor.html_url) {
+            img(src=contributor.avatar_url, title=contributor.login, class="rounded-full w-12 h-12 hover:shadow-lg transition", loading="lazy")
+        }
+    }
+}
+
 #[cfg_ssr]
 #[component]
 fn CommunitySection() -> View {
@@ -177,11 +196,28 @@ fn CommunitySection() -> View {
     let stars_hundreds = (repo_stats.stargazers_count as f64 / 100.0).round() as u32;
     let stars_text = format!("{}.{}k", stars_hundreds / 10, stars_hundreds % 10);
 
-    let contributors = crate::api_stats::get_contributors();
+    let mut contributors = crate::api_stats::get_contributors();
     let contributors_len = contributors.len();
+    // The rest of the contributors are hidden until they are expanded.
+    let more_contributors = contributors.split_off(SHOWN_CONTRIBUTORS.min(contributors_len));
+    let more_contributors = (!more_contributors.is_empty()).then(|| {
+        let summary = format!("+{} more", more_contributors.len());
+        view! {
+            details(class="mx-auto my-2 sm:max-w-[800px]") {
+                summary(class="cursor-pointer underline") { (summary) }
+                div(class="mt-2 flex flex-wrap justify-center gap-2") {
+                    Indexed(list=more_contributors, view=|contributor| view! { ContributorAvatar(contributor=contributor) })
+                }
+            }
+        }
+    });
 
     let crates_io_downloads = crate::api_stats::get_crate_io_stats()._crate.downloads;
 
+    let repository_url = crate::config::site_config().repository_url();
+    let contributors_url = format!("{repository_url}/graphs/contributors");
+    let contributing_url = format!("{repository_url}/blob/main/CONTRIBUTING.md");
+
     view! {
         div(class="grid grid-rows-3 sm:grid-rows-1 sm:grid-cols-3 text-2xl font-bold text-center divide-gray-200 divide-y-2 sm:divide-y-0 sm:divide-x-2 divide-solid rounded-lg max-w-[1000px] mx-auto") {
             div(class="px-4") {
@@ -198,52 +234,37 @@ fn CommunitySection() -> View {
             }
         }
         div(class="mt-5 text-center") {
-            p { "Sycamore is made possible by all our " a(class="underline", href="https://github.com/sycamore-rs/sycamore/graphs/contributors") { "community contributors" } ". Thank you!" }
+            p { "Sycamore is made possible by all our " a(class="underline", href=contributors_url) { "community contributors" } ". Thank you!" }
 
             div(class="mx-auto my-2 sm:max-w-[800px] flex flex-wrap justify-center gap-2") {
-                Indexed(
-                    list=contributors,
-                    view=|contributor| view! {
-                        a(href=contributor.html_url) {
-                            img(src=contributor.avatar_url, title=contributor.login, class="rounded-full w-12 h-12 hover:shadow-lg transition", loading="lazy")
-                        }
-                    }
-                )
+                Indexed(list=contributors, view=|contributor| view! { ContributorAvatar(contributor=contributor) })
             }
+            (more_contributors)
 
-            p { "Interested in contributing as well? Check out our " a(class="underline", href="https://github.com/sycamore-rs/sycamore/blob/main/CONTRIBUTING.md") { "contribution guide" } "." }
+            p { "Interested in contributing as well? Check out our " a(class="underline", href=contributing_url) { "contribution guide" } "." }
         }
     }
 }
 
+/// The number of posts that are shown on the index page. The rest are on the blog.
+#[cfg_ssr]
+const SHOWN_POSTS: usize = 5;
+
 #[cfg_ssr]
 #[component]
 fn NewsList() -> View {
-    // Sort posts by date.
-    let mut posts = crate::content::POSTS
-        .iter()
-        .map(|(id, post)| (id.clone(), post.clone()))
-        .collect::<Vec<_>>();
-    posts.sort_by_key(|(_, post)| post.front_matter.date);
+    use crate::pages::blog::PostSummary;
 
-    posts
+    let posts = crate::content::sorted_posts()
         .into_iter()
-        .rev()
-        .map(|(id, post)| {
-            view! {
-                a(href=format!("/post/{id}"), class="mt-5") {
-                    p(class="text-xs") {
-                        (post.front_matter.date.to_string())
-                    }
-                    p(class="text-2xl font-semibold") {
-                        (post.front_matter.title.clone())
-                    }
-                    p(class="text-gray-800 dark:text-gray-400") {
-                        (post.front_matter.description.clone())
-                    }
-                }
-            }
-        })
-        .collect::<Vec<_>>()
-        .into()
+        .take(SHOWN_POSTS)
+        .map(|(id, post)| view! { PostSummary(id=id.clone(), post=post.clone()) })
+        .collect::<Vec<_>>();
+
+    view! {
+        div(class="flex flex-col", data-prefetch=crate::prefetch::VISIBLE) {
+            (posts)
+            a(href="/blog", class="mt-5 underline") { "All posts" }
+        }
+    }
 }
@@ -1,3 +1,4 @@
+pub mod blog;
 pub mod book;
 pub mod index;
 pub mod not_found;
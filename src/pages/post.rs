@@ -1,11 +1,11 @@
 use sycamore::prelude::*;
 
-use crate::server_component::ServerOnly;
+use crate::{server_component::ServerOnly, shell::server_component_id};
 
 #[component(inline_props)]
 pub fn Post(id: String) -> View {
     view! {
-        ServerOnly(id=format!("Post_{id}"), on_load=|| { crate::utils::prism_highlight_all(); }) {
+        ServerOnly(id=server_component_id()) {
             PostBody(id=id)
         }
     }
@@ -21,19 +21,59 @@ pub fn PostBody(id: String) -> View {
 #[cfg_ssr]
 #[component(inline_props)]
 pub fn PostBody(id: String) -> View {
-    use crate::server_component::ServerTitle;
+    use crate::{
+        config::site_config,
+        og_image,
+        server_component::ServerTitle,
+        shell::{set_page_meta, PageAuthor, PageImage, PageMeta},
+    };
 
     let parsed = crate::content::POSTS
         .get(&id)
         .expect("post not found")
         .clone();
 
+    let authors = crate::content::post_authors(&parsed.front_matter);
+
+    let base_url = &site_config().base_url;
+    set_page_meta(PageMeta {
+        description: Some(parsed.front_matter.description.clone()),
+        canonical_url: Some(format!("{base_url}/post/{id}")),
+        // Unlisted posts do not get a preview image.
+        image: (!parsed.front_matter.unlisted).then(|| PageImage {
+            url: format!("{base_url}{}", og_image::image_path(&format!("/post/{id}"))),
+            width: og_image::WIDTH,
+            height: og_image::HEIGHT,
+        }),
+        published_time: Some(parsed.front_matter.date.to_rfc3339()),
+        authors: authors
+            .iter()
+            .map(|author| PageAuthor {
+                name: author.name.clone(),
+                url: author.github_url(),
+            })
+            .collect(),
+    });
+
+    let draft_banner = parsed.front_matter.draft.then(|| {
+        view! {
+            div(class="not-prose mb-4 px-4 py-2 rounded-lg bg-yellow-100 text-yellow-900 dark:bg-yellow-900 dark:text-yellow-100") {
+                i(class="bi bi-exclamation-triangle-fill") " This post is a draft and has not been published yet."
+            }
+        }
+    });
+
     view! {
         ServerTitle(title=parsed.front_matter.title.clone())
         div(class="flex flex-row gap-0 sm:gap-4 w-full justify-center") {
             div(class="flex-none w-40 hidden lg:block") // Empty block used for spacing
             div(class="grow-0 min-w-0 px-2 pt-5 pb-10 prose md:w-[80ch] dark:prose-invert") {
+                (draft_banner)
                 span(class="text-sm") { (parsed.front_matter.date.to_string()) }
+                crate::pages::blog::Byline(authors=authors)
+                div(class="not-prose mt-2") {
+                    crate::pages::blog::TagChips(tags=parsed.front_matter.tags.clone())
+                }
                 mdsycx::MDSycX(body=parsed.body)
             }
             crate::utils::HeadingsOutline(headings=parsed.headings)
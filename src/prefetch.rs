@@ -0,0 +1,179 @@
+//! Prefetching of the server components of linked pages.
+//!
+//! Links inside of an element with the [`PREFETCH_ATTR`] attribute are prefetched when they are
+//! hovered or focused. If the value of the attribute is [`VISIBLE`], they are also prefetched as
+//! soon as they are scrolled into view. The fragments are kept in a small cache that
+//! [`ServerOnly`](crate::server_component::ServerOnly) checks before going to the network, so that
+//! navigating to a prefetched page does not have to wait for it.
+
+use sycamore::prelude::cfg_not_ssr;
+
+/// The attribute that marks an element whose links are prefetched.
+pub static PREFETCH_ATTR: &str = "data-prefetch";
+
+/// Only prefetch the links when they are hovered or focused, e.g. for the long list of links in
+/// the sidebar of the book.
+pub static HOVER: &str = "hover";
+
+/// Also prefetch the links when they are scrolled into view.
+pub static VISIBLE: &str = "visible";
+
+#[cfg_not_ssr]
+pub use self::client::*;
+
+#[cfg_not_ssr]
+mod client {
+    use std::{
+        cell::RefCell,
+        collections::{HashSet, VecDeque},
+    };
+
+    use sycamore::prelude::*;
+    use sycamore::web::{
+        js_sys::Array,
+        wasm_bindgen::{prelude::Closure, JsCast, UnwrapThrowExt},
+    };
+    use sycamore_router::Route;
+    use web_sys::{
+        Element, Event, HtmlAnchorElement, IntersectionObserver, IntersectionObserverEntry,
+    };
+
+    use super::{PREFETCH_ATTR, VISIBLE};
+    use crate::{
+        server_component::{fetch_fragment, fragment_url},
+        Routes,
+    };
+
+    /// The number of fragments that are kept in the cache.
+    const CACHE_SIZE: usize = 32;
+
+    /// A least recently used cache from the URLs of fragments to their HTML.
+    #[derive(Debug, Default)]
+    struct Cache {
+        /// The most recently used entry is at the front.
+        entries: VecDeque<(String, String)>,
+    }
+
+    impl Cache {
+        fn contains(&self, url: &str) -> bool {
+            self.entries.iter().any(|(key, _)| key == url)
+        }
+
+        fn get(&mut self, url: &str) -> Option<String> {
+            let i = self.entries.iter().position(|(key, _)| key == url)?;
+            let entry = self.entries.remove(i)?;
+            self.entries.push_front(entry);
+            Some(self.entries[0].1.clone())
+        }
+
+        fn insert(&mut self, url: String, html: String) {
+            self.entries.retain(|(key, _)| *key != url);
+            self.entries.push_front((url, html));
+            self.entries.truncate(CACHE_SIZE);
+        }
+    }
+
+    thread_local! {
+        static CACHE: RefCell<Cache> = RefCell::default();
+        /// The URLs of the fragments that are being prefetched.
+        static PENDING: RefCell<HashSet<String>> = RefCell::default();
+        static OBSERVER: IntersectionObserver = {
+            let callback = Closure::<dyn Fn(Array, IntersectionObserver)>::new(
+                |entries: Array, observer: IntersectionObserver| {
+                    for entry in entries.iter() {
+                        let entry = entry.unchecked_into::<IntersectionObserverEntry>();
+                        if entry.is_intersecting() {
+                            observer.unobserve(&entry.target());
+                            prefetch_link(&entry.target().unchecked_into());
+                        }
+                    }
+                },
+            );
+            let observer =
+                IntersectionObserver::new(callback.as_ref().unchecked_ref()).unwrap_throw();
+            callback.forget();
+            observer
+        };
+    }
+
+    /// Get the HTML of the fragment at `url` if it has been prefetched.
+    pub fn cached(url: &str) -> Option<String> {
+        CACHE.with_borrow_mut(|cache| cache.get(url))
+    }
+
+    /// Fetch the fragment at `url` into the cache, unless it is already there.
+    pub fn prefetch(url: String) {
+        if CACHE.with_borrow(|cache| cache.contains(&url))
+            || !PENDING.with_borrow_mut(|pending| pending.insert(url.clone()))
+        {
+            return;
+        }
+        sycamore::futures::spawn_local(async move {
+            match fetch_fragment(&url).await {
+                Ok(html) => CACHE.with_borrow_mut(|cache| cache.insert(url.clone(), html)),
+                // Navigating to the page will try again.
+                Err(err) => {
+                    web_sys::console::warn_1(&format!("could not prefetch `{url}`: {err}").into())
+                }
+            }
+            PENDING.with_borrow_mut(|pending| pending.remove(&url));
+        });
+    }
+
+    /// Prefetch the server component of the page that `a` links to, if it is another page of the
+    /// site.
+    fn prefetch_link(a: &HtmlAnchorElement) {
+        let location = window().location();
+        if location.origin().as_ref() != Ok(&a.origin())
+            || location.pathname().as_ref() == Ok(&a.pathname())
+        {
+            return;
+        }
+        if let Some(id) = Routes::default()
+            .match_path(&a.pathname())
+            .server_component_id()
+        {
+            prefetch(fragment_url(&id));
+        }
+    }
+
+    /// Prefetch the links that are hovered or focused. This only needs to be called once.
+    pub fn listen() {
+        let callback = Closure::<dyn Fn(Event)>::new(|ev: Event| {
+            if let Some(a) = ev
+                .target()
+                .and_then(|target| target.dyn_into::<Element>().ok())
+                .and_then(|element| {
+                    element
+                        .closest(&format!("[{PREFETCH_ATTR}] a[href]"))
+                        .ok()
+                        .flatten()
+                })
+            {
+                prefetch_link(&a.unchecked_into());
+            }
+        });
+        for event in ["mouseover", "focusin"] {
+            document()
+                .add_event_listener_with_callback(event, callback.as_ref().unchecked_ref())
+                .unwrap_throw();
+        }
+        callback.forget();
+    }
+
+    /// Prefetch the links in `container` that should be prefetched once they are visible. Only
+    /// the links of the last container are observed, since the previous page is gone by then.
+    pub fn observe(container: &Element) {
+        OBSERVER.with(|observer| {
+            observer.disconnect();
+            let links = container
+                .query_selector_all(&format!("[{PREFETCH_ATTR}={VISIBLE}] a[href]"))
+                .unwrap_throw();
+            for i in 0..links.length() {
+                if let Some(Ok(link)) = links.get(i).map(JsCast::dyn_into::<Element>) {
+                    observer.observe(&link);
+                }
+            }
+        });
+    }
+}
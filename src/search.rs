@@ -0,0 +1,472 @@
+//! Full-text search over the book and the blog.
+//!
+//! At build time, every doc and post is split up into sections at each heading. The sections are
+//! written to `/search/docs.json` and an inverted index from terms to sections is written to
+//! `/search/{shard}.json`, sharded by the first character of each term. The client only fetches
+//! the index once the user starts searching, and only the shards for the terms that are searched.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sycamore::prelude::*;
+
+/// The directory, relative to the site root, in which the search index is stored.
+pub static SEARCH_DIR: &str = "search";
+
+/// Words that are too common to be useful for searching.
+static STOP_WORDS: &[&str] = &[
+    "an", "and", "are", "as", "at", "be", "by", "for", "if", "in", "is", "it", "of", "on", "or",
+    "so", "that", "the", "this", "to", "we", "with", "you",
+];
+
+/// A section of a page that can be returned as a search result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchDoc {
+    /// The URL of the page.
+    pub url: String,
+    /// The title of the page.
+    pub title: String,
+    /// The text of the heading at the start of this section, if any.
+    pub heading: Option<String>,
+    /// The id of the heading at the start of this section, if any.
+    pub anchor: Option<String>,
+    /// The text of this section, used for showing snippets.
+    pub text: String,
+}
+
+/// A shard of the inverted index. Maps each term to a list of `(doc, score)` pairs where `doc` is
+/// an index into the list of [`SearchDoc`]s.
+pub type Shard = BTreeMap<String, Vec<(u32, u32)>>;
+
+/// Split text into lowercase search terms.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+}
+
+/// Get the name of the shard that a term is stored in.
+pub fn shard_key(term: &str) -> char {
+    match term.chars().next() {
+        Some(c) if c.is_ascii_alphanumeric() => c,
+        _ => '_',
+    }
+}
+
+/// The generated search index.
+#[cfg_ssr]
+pub struct SearchIndex {
+    pub docs: Vec<SearchDoc>,
+    pub shards: BTreeMap<char, Shard>,
+}
+
+/// Split a parsed markdown body into sections at each heading.
+#[cfg_ssr]
+fn split_sections(url: &str, title: &str, body: &mdsycx::BodyRes) -> Vec<SearchDoc> {
+    use mdsycx::Event;
+
+    static INLINE_TAGS: &[&str] = &[
+        "a", "b", "code", "del", "em", "i", "kbd", "mark", "s", "span", "strong", "sub", "sup",
+    ];
+
+    let mut sections = vec![SearchDoc {
+        url: url.to_string(),
+        title: title.to_string(),
+        heading: None,
+        anchor: None,
+        text: String::new(),
+    }];
+    // The tags that are currently open.
+    let mut stack = Vec::new();
+    // The heading that is currently being parsed, along with its depth in the stack.
+    let mut heading: Option<(usize, String, Option<String>)> = None;
+
+    for event in crate::content::body_events(body) {
+        match event {
+            Event::Start(tag) => {
+                let is_heading = tag.len() == 2 && tag.starts_with('h');
+                if is_heading && tag[1..].chars().all(|c| c.is_ascii_digit()) {
+                    heading = Some((stack.len(), String::new(), None));
+                }
+                stack.push(tag);
+            }
+            Event::Attr(key, value) => {
+                if let Some((_, _, anchor)) = &mut heading {
+                    if key == "id" {
+                        *anchor = Some(value);
+                    }
+                }
+            }
+            Event::Text(text) => match &mut heading {
+                Some((_, heading_text, _)) => heading_text.push_str(&text),
+                None => sections.last_mut().unwrap().text.push_str(&text),
+            },
+            Event::End => {
+                let tag = stack.pop().unwrap_or_default();
+                // Separate the text of block elements.
+                if !INLINE_TAGS.contains(&tag.as_str()) {
+                    sections.last_mut().unwrap().text.push(' ');
+                }
+                if heading
+                    .as_ref()
+                    .is_some_and(|(depth, _, _)| *depth == stack.len())
+                {
+                    let (_, heading_text, anchor) = heading.take().unwrap();
+                    // The top-level heading is the title of the page, so it does not start a
+                    // new section.
+                    if tag != "h1" {
+                        sections.push(SearchDoc {
+                            url: url.to_string(),
+                            title: title.to_string(),
+                            heading: Some(heading_text),
+                            anchor,
+                            text: String::new(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    for section in &mut sections {
+        section.text = section
+            .text
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+    sections
+}
+
+/// Generate the search index for all the docs and posts.
+#[cfg_ssr]
+pub fn generate_search_index() -> SearchIndex {
+    use std::collections::HashMap;
+
+    use crate::content::{sorted_posts, DEFAULT_VERSION, DOCS};
+
+    let mut pages = Vec::new();
+    // Only the default version of the book is searchable.
+    for (page, doc) in DOCS.iter() {
+        if *page.version() == *DEFAULT_VERSION {
+            pages.push((page.href(false), &doc.front_matter.title, &doc.body));
+        }
+    }
+    // Unlisted posts are left out.
+    for (id, post) in sorted_posts() {
+        pages.push((format!("/post/{id}"), &post.front_matter.title, &post.body));
+    }
+    // Sort the pages so that the generated index is deterministic.
+    pages.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let docs = pages
+        .into_iter()
+        .flat_map(|(url, title, body)| split_sections(&url, title, body))
+        .collect::<Vec<_>>();
+
+    let mut shards = BTreeMap::<char, Shard>::new();
+    for (i, doc) in docs.iter().enumerate() {
+        let mut scores = HashMap::<String, u32>::new();
+        for term in tokenize(&doc.text) {
+            *scores.entry(term).or_default() += 1;
+        }
+        // Cap the score from the body so that very long sections do not dominate.
+        for score in scores.values_mut() {
+            *score = (*score).min(20);
+        }
+        // Boost matches in the title and headings.
+        if doc.heading.is_none() {
+            for term in tokenize(&doc.title) {
+                *scores.entry(term).or_default() += 30;
+            }
+        }
+        for term in doc.heading.iter().flat_map(|heading| tokenize(heading)) {
+            *scores.entry(term).or_default() += 15;
+        }
+
+        for (term, score) in scores {
+            shards
+                .entry(shard_key(&term))
+                .or_default()
+                .entry(term)
+                .or_default()
+                .push((i as u32, score));
+        }
+    }
+
+    SearchIndex { docs, shards }
+}
+
+/// A search result that is ready to be displayed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub href: String,
+    pub title: String,
+    pub heading: Option<String>,
+    /// The snippet of text around the first match, split into the text before the match, the
+    /// match itself, and the text after the match.
+    pub snippet: (String, String, String),
+}
+
+/// Find the snippet of text around the first occurrence of one of the terms.
+#[cfg_not_ssr]
+fn snippet(text: &str, terms: &[String]) -> (String, String, String) {
+    const BEFORE: usize = 40;
+    const AFTER: usize = 120;
+
+    // ASCII lowercasing preserves byte offsets.
+    let lowercase = text.to_ascii_lowercase();
+    let Some((start, len)) = terms
+        .iter()
+        .filter_map(|term| lowercase.find(term.as_str()).map(|i| (i, term.len())))
+        .min()
+    else {
+        let end = floor_char_boundary(text, BEFORE + AFTER);
+        let ellipsis = if end < text.len() { "…" } else { "" };
+        return (
+            format!("{}{ellipsis}", &text[..end]),
+            String::new(),
+            String::new(),
+        );
+    };
+    let end = start + len;
+
+    let mut before_start = floor_char_boundary(text, start.saturating_sub(BEFORE));
+    // Start the snippet at a word boundary.
+    if before_start > 0 {
+        if let Some(space) = text[before_start..start].find(' ') {
+            before_start += space + 1;
+        }
+    }
+    let after_end = floor_char_boundary(text, end + AFTER);
+    let before = if before_start > 0 {
+        format!("…{}", &text[before_start..start])
+    } else {
+        text[..start].to_string()
+    };
+    let after = if after_end < text.len() {
+        format!("{}…", &text[end..after_end])
+    } else {
+        text[end..].to_string()
+    };
+    (before, text[start..end].to_string(), after)
+}
+
+#[cfg_not_ssr]
+fn floor_char_boundary(text: &str, mut i: usize) -> usize {
+    if i >= text.len() {
+        return text.len();
+    }
+    while !text.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+/// Rank the docs that match all the terms. The last term also matches as a prefix, since the user
+/// might not have finished typing it yet.
+#[cfg_not_ssr]
+fn rank(docs: &[SearchDoc], terms: &[String], shards: &[std::rc::Rc<Shard>]) -> Vec<SearchResult> {
+    use std::collections::HashMap;
+
+    /// Maximum number of results that are shown.
+    const MAX_RESULTS: usize = 10;
+
+    // Map from doc index to the total score and the number of terms matched.
+    let mut matches = HashMap::<u32, (u32, usize)>::new();
+    for (i, (term, shard)) in terms.iter().zip(shards).enumerate() {
+        let is_last = i == terms.len() - 1;
+        let mut term_scores = HashMap::<u32, u32>::new();
+        for (candidate, postings) in shard.range(term.clone()..) {
+            let weight = if candidate == term {
+                2
+            } else if is_last && candidate.starts_with(term.as_str()) {
+                1
+            } else {
+                break;
+            };
+            for &(doc, score) in postings {
+                let entry = term_scores.entry(doc).or_default();
+                *entry = (*entry).max(score * weight);
+            }
+        }
+        for (doc, score) in term_scores {
+            let entry = matches.entry(doc).or_default();
+            entry.0 += score;
+            entry.1 += 1;
+        }
+    }
+
+    let mut matches = matches
+        .into_iter()
+        .filter(|(_, (_, count))| *count == terms.len())
+        .map(|(doc, (score, _))| (doc, score))
+        .collect::<Vec<_>>();
+    matches.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    matches
+        .into_iter()
+        .take(MAX_RESULTS)
+        .filter_map(|(doc, _)| docs.get(doc as usize))
+        .map(|doc| SearchResult {
+            href: match &doc.anchor {
+                Some(anchor) => format!("{}#{anchor}", doc.url),
+                None => doc.url.clone(),
+            },
+            title: doc.title.clone(),
+            heading: doc.heading.clone(),
+            snippet: snippet(&doc.text, terms),
+        })
+        .collect()
+}
+
+/// Fetching the search index on the client side.
+#[cfg_not_ssr]
+mod client {
+    use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+    use gloo_net::http::Request;
+
+    use super::*;
+
+    thread_local! {
+        static DOCS: RefCell<Option<Rc<Vec<SearchDoc>>>> = const { RefCell::new(None) };
+        static SHARDS: RefCell<HashMap<char, Rc<Shard>>> = RefCell::new(HashMap::new());
+    }
+
+    async fn fetch_json<T: for<'de> Deserialize<'de>>(url: &str) -> Result<T, gloo_net::Error> {
+        Request::get(url).send().await?.json().await
+    }
+
+    async fn load_docs() -> Result<Rc<Vec<SearchDoc>>, gloo_net::Error> {
+        if let Some(docs) = DOCS.with(|docs| docs.borrow().clone()) {
+            return Ok(docs);
+        }
+        let docs = Rc::new(fetch_json(&format!("/{SEARCH_DIR}/docs.json")).await?);
+        DOCS.with(|cache| *cache.borrow_mut() = Some(Rc::clone(&docs)));
+        Ok(docs)
+    }
+
+    async fn load_shard(key: char) -> Result<Rc<Shard>, gloo_net::Error> {
+        if let Some(shard) = SHARDS.with(|shards| shards.borrow().get(&key).cloned()) {
+            return Ok(shard);
+        }
+        let shard = match Request::get(&format!("/{SEARCH_DIR}/{key}.json"))
+            .send()
+            .await?
+        {
+            // Shards that do not have any terms are not generated.
+            res if res.status() == 404 => Shard::new(),
+            res => res.json().await?,
+        };
+        let shard = Rc::new(shard);
+        SHARDS.with(|shards| shards.borrow_mut().insert(key, Rc::clone(&shard)));
+        Ok(shard)
+    }
+
+    /// Search for the query, fetching the parts of the index that are needed.
+    pub async fn search(query: &str) -> Result<Vec<SearchResult>, gloo_net::Error> {
+        let terms = tokenize(query).collect::<Vec<_>>();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let docs = load_docs().await?;
+        let mut shards = Vec::new();
+        for term in &terms {
+            shards.push(load_shard(shard_key(term)).await?);
+        }
+        Ok(rank(&docs, &terms, &shards))
+    }
+}
+
+/// A search box that shows the results in a dropdown.
+#[component]
+pub fn SearchBox() -> View {
+    let query = create_signal(String::new());
+    let results = create_signal(Vec::<SearchResult>::new());
+    let open = create_signal(false);
+
+    is_not_ssr! {
+        create_effect(move || {
+            let current = query.get_clone();
+            sycamore::futures::spawn_local_scoped(async move {
+                match client::search(&current).await {
+                    // Ignore the results if the query changed in the meantime.
+                    Ok(res) if query.with_untracked(|query| *query == current) => results.set(res),
+                    Ok(_) => {}
+                    Err(err) => sycamore::web::console_error!("could not load search index: {err}"),
+                }
+            });
+        });
+    }
+
+    let on_keydown = move |ev: web_sys::KeyboardEvent| {
+        if ev.key() == "Escape" {
+            open.set(false);
+        }
+    };
+
+    view! {
+        div(class="relative", on:keydown=on_keydown) {
+            input(
+                r#type="search",
+                placeholder="Search…",
+                aria-label="Search the book and blog",
+                class="w-32 sm:w-64 px-3 py-1 text-sm rounded-full bg-white dark:bg-gray-800 border border-gray-300 dark:border-gray-700 focus:outline-none focus:border-orange-500",
+                bind:value=query,
+                on:focus=move |_| open.set(true),
+                on:blur=move |_| open.set(false),
+            )
+            (if open.get() && !query.with(String::is_empty) {
+                view! {
+                    // Prevent the input from losing focus before the link is clicked.
+                    ul(
+                        class="absolute right-0 mt-2 w-[calc(100vw-2rem)] sm:w-[32rem] max-h-[70vh] overflow-y-auto rounded-lg shadow-lg bg-white dark:bg-gray-900 border border-gray-200 dark:border-gray-800 text-sm",
+                        on:mousedown=|ev: web_sys::MouseEvent| ev.prevent_default(),
+                    ) {
+                        (if results.with(Vec::is_empty) {
+                            view! {
+                                li(class="px-3 py-2 text-gray-600 dark:text-gray-400") { "No results" }
+                            }
+                        } else {
+                            view! {}
+                        })
+                        Indexed(
+                            list=results,
+                            view=move |result| {
+                                let (before, matched, after) = result.snippet;
+                                let heading = result
+                                    .heading
+                                    .map(|heading| format!(" › {heading}"))
+                                    .unwrap_or_default();
+                                view! {
+                                    li {
+                                        a(
+                                            class="block px-3 py-2 hover:bg-gray-100 dark:hover:bg-gray-800",
+                                            href=result.href,
+                                            on:click=move |_| open.set(false),
+                                        ) {
+                                            p(class="font-semibold") {
+                                                (result.title)
+                                                (heading)
+                                            }
+                                            p(class="text-xs text-gray-600 dark:text-gray-400") {
+                                                (before)
+                                                mark(class="bg-orange-200 dark:bg-orange-800 dark:text-gray-100") { (matched) }
+                                                (after)
+                                            }
+                                        }
+                                    }
+                                }
+                            },
+                        )
+                    }
+                }
+            } else {
+                view! {}
+            })
+        }
+    }
+}
@@ -3,7 +3,7 @@
 //! Eventually consider upstreaming this into Sycamore or into a seperate crate.
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     sync::{LazyLock, Mutex},
 };
 
@@ -11,9 +11,180 @@ use sycamore::prelude::*;
 
 type ServerComponentMap = HashMap<String, String>;
 
-/// A map from server component ids to the rendered content.
+/// A map from server component ids to the rendered content. This is shared between all the
+/// threads that render pages.
 pub static SERVER_COMPONENTS: LazyLock<Mutex<ServerComponentMap>> = LazyLock::new(Default::default);
 
+/// A map from server component ids to the hash of their contents.
+pub type Manifest = BTreeMap<String, String>;
+
+/// The directory that the server components are written to.
+pub static SERVER_COMPONENTS_DIR: &str = "server_components";
+
+/// The id of the `<script>` tag that the manifest is embedded in.
+pub static MANIFEST_ID: &str = "server-component-manifest";
+
+/// The path of the file that the server component with the given `id` and content `hash` is
+/// written to. Since the path changes whenever the contents do, the files can be cached forever.
+pub fn fragment_path(id: &str, hash: &str) -> String {
+    format!("/{SERVER_COMPONENTS_DIR}/{id}.{hash}.html")
+}
+
+/// Hash the contents of a server component for [`fragment_path`].
+#[cfg_ssr]
+pub fn content_hash(html: &str) -> String {
+    format!("{:016x}", crate::build_cache::fingerprint(html))
+}
+
+/// Get the id and the hash of a server component from the path that it was written to. This is
+/// the inverse of [`fragment_path`].
+#[cfg_ssr]
+pub fn parse_fragment_path(path: &str) -> Option<(&str, &str)> {
+    path.strip_prefix('/')?
+        .strip_prefix(SERVER_COMPONENTS_DIR)?
+        .strip_prefix('/')?
+        .strip_suffix(".html")?
+        // Ids can contain dots, e.g. `VersionedBook/v0.9/introduction`, but hashes cannot.
+        .rsplit_once('.')
+}
+
+/// Replace the manifest that is embedded in the `html` of a page with `manifest_json`.
+#[cfg_ssr]
+pub fn embed_manifest(html: &str, manifest_json: &str) -> String {
+    let marker = format!("id=\"{MANIFEST_ID}\">");
+    let Some(start) = html.find(&marker).map(|i| i + marker.len()) else {
+        return html.to_string();
+    };
+    let end = start + html[start..].find("</script>").unwrap_or(0);
+    format!("{}{manifest_json}{}", &html[..start], &html[end..])
+}
+
+/// The manifest that was embedded into the page during SSR.
+#[cfg_not_ssr]
+static MANIFEST: LazyLock<Manifest> = LazyLock::new(|| {
+    document()
+        .get_element_by_id(MANIFEST_ID)
+        .and_then(|element| element.text_content())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+});
+
+/// Get the URL of the server component with the given `id`.
+#[cfg_not_ssr]
+pub fn fragment_url(id: &str) -> String {
+    match MANIFEST.get(id) {
+        Some(hash) => fragment_path(id, hash),
+        // This should not happen, but try the unhashed path just in case.
+        None => format!("/{SERVER_COMPONENTS_DIR}/{id}.html"),
+    }
+}
+
+/// How long to wait for a server component before giving up, in milliseconds.
+#[cfg_not_ssr]
+const FETCH_TIMEOUT_MS: i32 = 10_000;
+
+/// Why a server component could not be fetched.
+#[cfg_not_ssr]
+#[derive(Debug)]
+pub enum FetchError {
+    /// There is no server component at the URL, e.g. because it is from an older deploy.
+    NotFound,
+    /// The server responded with an error.
+    Status(u16),
+    /// The server did not respond within [`FETCH_TIMEOUT_MS`].
+    Timeout,
+    /// The request failed, e.g. because the network is down.
+    Network(gloo_net::Error),
+}
+
+#[cfg_not_ssr]
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "the server component does not exist"),
+            Self::Status(status) => write!(f, "the server responded with status {status}"),
+            Self::Timeout => write!(f, "the server did not respond in time"),
+            Self::Network(err) => write!(f, "the request failed: {err}"),
+        }
+    }
+}
+
+#[cfg_not_ssr]
+impl From<gloo_net::Error> for FetchError {
+    fn from(err: gloo_net::Error) -> Self {
+        Self::Network(err)
+    }
+}
+
+/// A timer that calls a function once, unless it is dropped before then.
+#[cfg_not_ssr]
+struct Timeout {
+    handle: i32,
+    // Kept alive until the timer is cleared, since JS calls it.
+    _callback: sycamore::web::wasm_bindgen::prelude::Closure<dyn FnMut()>,
+}
+
+#[cfg_not_ssr]
+impl Timeout {
+    fn new(millis: i32, f: impl FnOnce() + 'static) -> Self {
+        use sycamore::web::wasm_bindgen::{prelude::Closure, JsCast, UnwrapThrowExt};
+
+        let callback = Closure::once(f);
+        let handle = window()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(
+                callback.as_ref().unchecked_ref(),
+                millis,
+            )
+            .unwrap_throw();
+        Self {
+            handle,
+            _callback: callback,
+        }
+    }
+}
+
+#[cfg_not_ssr]
+impl Drop for Timeout {
+    fn drop(&mut self) {
+        window().clear_timeout_with_handle(self.handle);
+    }
+}
+
+/// Fetch the HTML of the server component at `url`, e.g. from [`fragment_url`].
+#[cfg_not_ssr]
+pub async fn fetch_fragment(url: &str) -> Result<String, FetchError> {
+    use sycamore::web::wasm_bindgen::UnwrapThrowExt;
+
+    // Abort the request, including reading the body, once the timeout is reached. The timer is
+    // cleared when this future completes or is dropped, e.g. because the user navigated again.
+    let controller = web_sys::AbortController::new().unwrap_throw();
+    let _timeout = Timeout::new(FETCH_TIMEOUT_MS, {
+        let controller = controller.clone();
+        move || controller.abort()
+    });
+
+    let result = async {
+        let response = gloo_net::http::Request::get(url)
+            .abort_signal(Some(&controller.signal()))
+            .send()
+            .await?;
+        match response.status() {
+            404 => Err(FetchError::NotFound),
+            status if !response.ok() => Err(FetchError::Status(status)),
+            _ => Ok(response.text().await?),
+        }
+    }
+    .await;
+
+    result.map_err(|err| {
+        if controller.signal().aborted() {
+            FetchError::Timeout
+        } else {
+            err
+        }
+    })
+}
+
 /// Only run the component at build-time/during SSR. On the client side, if not hydrating, this
 /// will fetch the component HTML over HTTP.
 #[component(inline_props)]
@@ -21,12 +192,8 @@ pub fn ServerOnly(
     /// The unique id of the server component.
     id: String,
     children: Children,
-    /// A function to run when the component loads.
-    #[prop(default, setter(transform = |f: impl Fn() + 'static| Some(Box::new(f) as Box<dyn Fn()>)))]
-    on_load: Option<Box<dyn Fn()>>,
 ) -> View {
     is_ssr! {
-        let _ = on_load;
         // Render the children, as well as adding it to SERVER_COMPONENTS.
         let mut children = Some(children);
         let view = view! {
@@ -35,44 +202,118 @@ pub fn ServerOnly(
             }
         };
         let html = sycamore::render_to_string_in_scope(|| view);
-        SERVER_COMPONENTS.lock().unwrap().insert(id.clone(), html.clone());
+        // The fragment also carries the head of the page, which is only known once the children
+        // have been rendered. It is not needed in the page itself, which already has the head.
+        let fragment = format!(
+            "<template {}>{}</template>{html}",
+            crate::shell::PAGE_HEAD_ATTR,
+            crate::shell::page_head_html(),
+        );
+        // The same server component can be rendered by more than one page, possibly on different
+        // threads. This is fine as long as they all agree on the contents.
+        let previous = SERVER_COMPONENTS.lock().unwrap().insert(id.clone(), fragment.clone());
+        assert!(
+            previous.is_none_or(|previous| previous == fragment),
+            "server component `{id}` was rendered with different contents"
+        );
 
         view! {
             server-component(data-component=id, dangerously_set_inner_html=html)
         }
     }
     is_not_ssr! {
-        use gloo_net::http::Request;
         use sycamore::web::{AsHtmlNode, ViewHtmlNode, wasm_bindgen::JsCast};
 
         let _ = children;
 
         let container = create_node_ref();
+        // Why the component could not be fetched, if it could not.
+        let error = create_signal(None::<FetchError>);
+        let mut fallback = View::default();
 
         // Fetch the component HTML over HTTP if we are not hydrating.
         if !sycamore::web::is_hydrating() {
-            let url = format!("/server_components/{id}.html");
-            sycamore::futures::create_suspense_task(async move {
-                let html = Request::get(&url).send().await.expect("could not send HTTP request").text().await.expect("could not get text from response");
-                sycamore::web::DomNode::from_web_sys(container.get()).set_inner_html(html.into());
-
-                // Recreate all the script tags so that they run.
-                let scripts = container.get().unchecked_into::<web_sys::Element>().query_selector_all("script").unwrap();
-                let n = scripts.length();
-                for i in 0..n {
-                    let script = scripts.get(i).unwrap().unchecked_into::<web_sys::Element>();
-                    let mut new_script = sycamore::web::tags::script().dangerously_set_inner_html(script.inner_html());
-                    document().body().unwrap().append_child(&new_script.as_html_node().as_web_sys()).unwrap();
-                }
+            let url = fragment_url(&id);
+            let load = move || {
+                let url = url.clone();
+                async move {
+                    // The fragment might already have been prefetched.
+                    let html = match crate::prefetch::cached(&url) {
+                        Some(html) => Ok(html),
+                        None => fetch_fragment(&url).await,
+                    };
+                    let html = match html {
+                        Ok(html) => html,
+                        Err(err) => {
+                            web_sys::console::error_1(&format!("could not fetch `{url}`: {err}").into());
+                            if matches!(err, FetchError::NotFound) {
+                                // The fragment might be gone because the site was deployed again
+                                // since the page was loaded. Let the server decide whether the
+                                // page still exists.
+                                crate::navigation::full_load();
+                            } else {
+                                error.set(Some(err));
+                            }
+                            return;
+                        }
+                    };
+                    sycamore::web::DomNode::from_web_sys(container.get()).set_inner_html(html.into());
+
+                    // Recreate all the script tags so that they run.
+                    let scripts = container.get().unchecked_into::<web_sys::Element>().query_selector_all("script").unwrap();
+                    let n = scripts.length();
+                    for i in 0..n {
+                        let script = scripts.get(i).unwrap().unchecked_into::<web_sys::Element>();
+                        let mut new_script = sycamore::web::tags::script().dangerously_set_inner_html(script.inner_html());
+                        document().body().unwrap().append_child(new_script.as_html_node().as_web_sys()).unwrap();
+                    }
 
-                if let Some(on_load) = on_load {
-                    on_mount(on_load);
+                    crate::prefetch::observe(&container.get().unchecked_into());
+                    crate::navigation::fragment_loaded(&container.get().unchecked_into());
                 }
+            };
+            sycamore::futures::create_suspense_task(load());
+
+            // Retrying runs in the scope of the component so that it is cancelled if the page is
+            // left in the meantime.
+            let scope = use_current_scope();
+            fallback = View::from(move || {
+                error.with(|err| match err {
+                    None => view! {},
+                    Some(err) => {
+                        let load = load.clone();
+                        let retry = move |_| {
+                            error.set(None);
+                            scope.run_in(|| sycamore::futures::create_suspense_task(load()));
+                        };
+                        view! {
+                            FetchFailed(error=err.to_string(), retry=retry)
+                        }
+                    }
+                })
             });
+        } else {
+            on_mount(move || crate::prefetch::observe(&container.get().unchecked_into()));
         }
 
         view! {
             server-component(data-component=id, r#ref=container)
+            (fallback)
+        }
+    }
+}
+
+/// Shown instead of a server component that could not be fetched.
+#[cfg_not_ssr]
+#[component(inline_props)]
+fn FetchFailed(error: String, retry: impl Fn(web_sys::MouseEvent) + 'static) -> View {
+    view! {
+        div(class="mt-8 mx-auto px-2 sm:px-0 prose prose-lg dark:prose-invert") {
+            h1 { "Could not load this page" }
+            p { "Loading the page failed because " (error) ". Check your connection and try again." }
+            button(class="px-4 py-1 rounded bg-orange-400 dark:bg-orange-500 hover:bg-orange-500 dark:hover:bg-orange-600 transition-colors", on:click=retry) {
+                i(class="bi bi-arrow-clockwise") " Retry"
+            }
         }
     }
 }
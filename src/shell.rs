@@ -2,8 +2,9 @@ use sycamore::prelude::*;
 use sycamore_router::Route;
 
 use crate::{
+    config::{site_config, SITE_CONFIG_ID},
     layout::{self, DarkMode},
-    pages,
+    pages, server_component,
 };
 
 #[derive(Debug, Clone, PartialEq, Route)]
@@ -12,14 +13,121 @@ pub enum Routes {
     Index,
     #[to("/post/<id>")]
     Post(String),
+    #[to("/blog")]
+    Blog,
+    #[to("/blog/<page>")]
+    BlogPage(u32),
+    #[to("/blog/tag/<tag>")]
+    BlogTag(String),
+    #[to("/blog/author/<handle>")]
+    BlogAuthor(String),
     #[to("/book/<section>")]
     BookSection(String),
+    // Must come before `BookSubsection` since `Version` only matches version names.
+    #[to("/book/<version>/<section>")]
+    VersionedBookSection(Version, String),
     #[to("/book/<section>/<doc>")]
     BookSubsection(String, String),
+    #[to("/book/<version>/<section>/<doc>")]
+    VersionedBookSubsection(Version, String, String),
     #[not_found]
     NotFound,
 }
 
+impl Routes {
+    /// The id of the server component that renders the page. The pages pass this to
+    /// [`ServerOnly`](crate::server_component::ServerOnly) with [`server_component_id`], so that
+    /// the client can prefetch the server component of a link from its route. `None` if the page
+    /// is not a server component.
+    ///
+    /// Every route has a different id, since the parts are joined with `/`, which cannot appear
+    /// inside of a segment of a URL, and each kind of route has its own prefix.
+    pub fn server_component_id(&self) -> Option<String> {
+        let id = match self {
+            Self::Index => "IndexBody".to_string(),
+            Self::Post(id) => format!("Post/{id}"),
+            Self::Blog => "Blog/1".to_string(),
+            Self::BlogPage(page) => format!("Blog/{page}"),
+            Self::BlogTag(tag) => format!("BlogTag/{tag}"),
+            Self::BlogAuthor(handle) => format!("BlogAuthor/{handle}"),
+            Self::BookSection(section) => format!("Book/{section}"),
+            Self::VersionedBookSection(version, section) => {
+                format!("VersionedBook/{version}/{section}")
+            }
+            Self::BookSubsection(section, doc) => format!("Book/{section}/{doc}"),
+            Self::VersionedBookSubsection(version, section, doc) => {
+                format!("VersionedBook/{version}/{section}/{doc}")
+            }
+            Self::NotFound => return None,
+        };
+        Some(id)
+    }
+}
+
+/// A version of the book, e.g. `next` or `v0.9`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Version(String);
+
+impl Version {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether this is the version of the docs for the unreleased version of Sycamore.
+    pub fn is_unreleased(&self) -> bool {
+        self.0 == "next"
+    }
+
+    /// The numeric components of the version, e.g. `[0, 9]` for `v0.9`.
+    fn components(&self) -> Vec<u32> {
+        self.0
+            .trim_start_matches('v')
+            .split('.')
+            .map(|x| x.parse().unwrap_or_default())
+            .collect()
+    }
+}
+
+impl std::str::FromStr for Version {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_version = s == "next"
+            || s.strip_prefix('v')
+                .is_some_and(|v| v.starts_with(|c: char| c.is_ascii_digit()));
+        if is_version {
+            Ok(Self(s.to_string()))
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Versions are ordered from newest to oldest, with `next` first. Versions whose components are
+/// the same, e.g. `v0.9` and `v0.09`, are ordered by their name so that the order is consistent
+/// with [`Eq`].
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other
+            .is_unreleased()
+            .cmp(&self.is_unreleased())
+            .then_with(|| other.components().cmp(&self.components()))
+            .then_with(|| self.0.cmp(&other.0))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 /// Context value for setting the document title.
 /// Also renders a `<title>` tag in SSR.
 #[derive(Debug, Clone, Copy)]
@@ -30,10 +138,203 @@ pub fn set_title(title: impl Into<String>) {
     use_context::<Title>().0.set(title.into());
 }
 
+/// The default description of a page if it does not set its own.
+static DEFAULT_DESCRIPTION: &str = "Sycamore is a next generation Rust UI library powered by fine-grained reactivity. Create reactive apps with effortless performance";
+
+/// Metadata about a page for search engines and link previews.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageMeta {
+    /// A short summary of the page.
+    pub description: Option<String>,
+    /// The absolute URL of the page.
+    pub canonical_url: Option<String>,
+    /// The preview image that is shown when the page is shared.
+    pub image: Option<PageImage>,
+    /// The date that the page was published at, in RFC 3339 format. If this is set, the page is
+    /// an article.
+    pub published_time: Option<String>,
+    /// The authors of the article.
+    pub authors: Vec<PageAuthor>,
+}
+
+/// An author of an article.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageAuthor {
+    pub name: String,
+    /// The absolute URL of the profile of the author.
+    pub url: String,
+}
+
+/// An image for link previews.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageImage {
+    /// The absolute URL of the image.
+    pub url: String,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Context value for the metadata of the page.
+/// Rendered as `<meta>` tags in SSR.
+#[derive(Debug, Clone, Copy)]
+struct PageMetaContext(Signal<PageMeta>);
+
+/// Set the metadata of the page.
+pub fn set_page_meta(meta: PageMeta) {
+    use_context::<PageMetaContext>().0.set(meta);
+}
+
+/// Context value for the previous and next pages in reading order.
+/// Rendered as `<link rel="prev">` and `<link rel="next">` tags in SSR.
+#[derive(Debug, Clone, Copy)]
+struct PrevNext(Signal<(Option<String>, Option<String>)>);
+
+/// Set the URLs of the previous and next pages.
+pub fn set_prev_next(prev: Option<String>, next: Option<String>) {
+    use_context::<PrevNext>().0.set((prev, next));
+}
+
+/// The attribute that marks the tags in the head that belong to the current page, so that they can
+/// be replaced when navigating to another page. See [`page_head_html`].
+pub static PAGE_HEAD_ATTR: &str = "data-page-head";
+
+/// Renders the tags in the head that depend on the page, i.e. the description, canonical URL,
+/// Open Graph and Twitter card tags, and the links to the previous and next pages.
+#[component(inline_props)]
+fn PageHead(
+    title: String,
+    meta: PageMeta,
+    /// The URLs of the previous and next pages.
+    prev_next: (Option<String>, Option<String>),
+) -> View {
+    let (prev, next) = prev_next;
+    let description = meta
+        .description
+        .unwrap_or_else(|| DEFAULT_DESCRIPTION.to_string());
+    let title = if title.is_empty() {
+        "Sycamore".to_string()
+    } else {
+        title
+    };
+    let og_type = if meta.published_time.is_some() {
+        "article"
+    } else {
+        "website"
+    };
+
+    let twitter_card = if meta.image.is_some() {
+        "summary_large_image"
+    } else {
+        "summary"
+    };
+
+    let mut names = vec![
+        ("description", description.clone()),
+        ("twitter:card", twitter_card.to_string()),
+        ("twitter:title", title.clone()),
+        ("twitter:description", description.clone()),
+    ];
+    let mut properties = vec![
+        ("og:site_name", "Sycamore".to_string()),
+        ("og:title", title.clone()),
+        ("og:description", description.clone()),
+        ("og:type", og_type.to_string()),
+    ];
+    if let Some(url) = &meta.canonical_url {
+        properties.push(("og:url", url.clone()));
+    }
+    if let Some(image) = &meta.image {
+        names.push(("twitter:image", image.url.clone()));
+        properties.push(("og:image", image.url.clone()));
+        properties.push(("og:image:width", image.width.to_string()));
+        properties.push(("og:image:height", image.height.to_string()));
+    }
+    // Structured data for search engines.
+    let json_ld = meta.published_time.as_ref().map(|time| {
+        let authors = meta
+            .authors
+            .iter()
+            .map(|author| {
+                serde_json::json!({
+                    "@type": "Person",
+                    "name": author.name,
+                    "url": author.url,
+                })
+            })
+            .collect::<Vec<_>>();
+        let json = serde_json::json!({
+            "@context": "https://schema.org",
+            "@type": "BlogPosting",
+            "headline": title,
+            "description": description,
+            "datePublished": time,
+            "url": meta.canonical_url,
+            "image": meta.image.as_ref().map(|image| &image.url),
+            "author": authors,
+        })
+        .to_string()
+        // Make sure that the JSON cannot close the script tag.
+        .replace("</", "<\\/");
+        view! { script(r#type="application/ld+json", data-page-head="", dangerously_set_inner_html=json) }
+    });
+
+    if let Some(time) = meta.published_time {
+        properties.push(("article:published_time", time));
+    }
+    for author in meta.authors {
+        properties.push(("article:author", author.url));
+    }
+
+    let canonical = meta
+        .canonical_url
+        .map(|url| view! { link(rel="canonical", href=url, data-page-head="") });
+    let names = names
+        .into_iter()
+        .map(|(name, content)| view! { meta(name=name, content=content, data-page-head="") })
+        .collect::<Vec<_>>();
+    let properties = properties
+        .into_iter()
+        .map(|(property, content)| {
+            view! { meta("property"=property, content=content, data-page-head="") }
+        })
+        .collect::<Vec<_>>();
+    let prev = prev.map(|href| view! { link(rel="prev", href=href, data-page-head="") });
+    let next = next.map(|href| view! { link(rel="next", href=href, data-page-head="") });
+
+    view! {
+        (names)
+        (canonical)
+        (properties)
+        (json_ld)
+        (prev)
+        (next)
+    }
+}
+
+/// Render the [`PageHead`] of the current page. This is sent along with the server component of
+/// the page, so that the head can be updated after navigating to it on the client.
+#[cfg_ssr]
+pub fn page_head_html() -> String {
+    let title = use_context::<Title>().0.get_clone();
+    let meta = use_context::<PageMetaContext>().0.get_clone();
+    let prev_next = use_context::<PrevNext>().0.get_clone();
+    sycamore::render_to_string_in_scope(|| {
+        view! {
+            sycamore::web::NoHydrate {
+                PageHead(title=title, meta=meta, prev_next=prev_next)
+            }
+        }
+    })
+}
+
 #[component(inline_props)]
 pub fn Shell(children: Children) -> View {
     let title = Title(create_signal(String::new()));
     provide_context(title);
+    let prev_next = PrevNext(create_signal((None, None)));
+    provide_context(prev_next);
+    let page_meta = PageMetaContext(create_signal(PageMeta::default()));
+    provide_context(page_meta);
 
     let dark_mode = if is_ssr!() {
         false
@@ -80,6 +381,11 @@ pub fn Shell(children: Children) -> View {
     // Call the children now so that we can get the title before we render the head.
     let children = children.call();
     let title_static = title.0.get_clone();
+    let prev_next_static = prev_next.0.get_clone();
+    let page_meta_static = page_meta.0.get_clone();
+    let page_meta_title = title_static.clone();
+    let analytics_id = site_config().analytics_id.clone();
+    let site_config_json = site_config().to_json();
 
     view! {
         html(lang="en", "data-color-scheme"=if dark_mode.0.get() { "dark" } else { "light" }) {
@@ -88,7 +394,7 @@ pub fn Shell(children: Children) -> View {
                     meta(charset="utf-8")
                     meta(name="viewport", content="width=device-width, initial-scale=1")
 
-                    meta(name="description", content="Sycamore is a next generation Rust UI library powered by fine-grained reactivity. Create reactive apps with effortless performance")
+                    PageHead(title=page_meta_title, meta=page_meta_static, prev_next=prev_next_static)
 
                     title { (title_static) }
 
@@ -101,16 +407,27 @@ pub fn Shell(children: Children) -> View {
                     link(rel="stylesheet", href="/index.css")
                     link(rel="icon", href="/favicon.ico")
 
+                    // Feeds
+                    link(rel="alternate", r#type="application/rss+xml", "title"="Sycamore Blog", href="/feed.xml")
+                    link(rel="alternate", r#type="application/atom+xml", "title"="Sycamore Blog", href="/atom.xml")
+
                     // Bootstrap Icons
                     link(rel="stylesheet", href="/icons/bootstrap-icons.min.css")
                     link(rel="preload", href="/icons/fonts/bootstrap-icons.woff2?dd67030699838ea613ee6dbda90effa6", r#as="font", r#type="font/woff2", crossorigin="anonymous")
 
-                    // PrismJS
-                    script(src="/prism/prism.js")
+                    // Syntax highlighting theme
                     link(rel="stylesheet", href="/prism/prism-gruvbox-dark.css")
 
                     // Analytics
-                    script(defer=true, src="https://sycamore-analytics.netlify.app/script.js", data-website-id="e539dd59-791b-44a2-9df4-18db88d9cb80")
+                    (analytics_id.clone().map(|id| view! {
+                        script(defer=true, src="https://sycamore-analytics.netlify.app/script.js", data-website-id=id)
+                    }))
+
+                    // The configuration of the site for the client.
+                    script(r#type="application/json", id=SITE_CONFIG_ID, dangerously_set_inner_html=site_config_json)
+                    // The hashes of the server components. This is filled in once all the pages
+                    // have been rendered.
+                    script(r#type="application/json", id=server_component::MANIFEST_ID, dangerously_set_inner_html="{}")
 
                     sycamore::web::HydrationScript {}
 
@@ -141,6 +458,15 @@ pub fn Shell(children: Children) -> View {
 #[derive(Debug, Clone, Copy)]
 pub struct CurrentRoute(pub ReadSignal<Routes>);
 
+/// The id of the server component of the current page, for passing to
+/// [`ServerOnly`](crate::server_component::ServerOnly). See [`Routes::server_component_id`].
+pub fn server_component_id() -> String {
+    use_context::<CurrentRoute>()
+        .0
+        .with_untracked(Routes::server_component_id)
+        .expect("the current page is not a server component")
+}
+
 #[component]
 pub fn App(route: ReadSignal<Routes>) -> View {
     provide_context(CurrentRoute(route));
@@ -149,8 +475,14 @@ pub fn App(route: ReadSignal<Routes>) -> View {
             (match route.get_clone() {
                 Routes::Index => pages::index::Index(),
                 Routes::Post(id) => view! { pages::post::Post(id=id) },
+                Routes::Blog => view! { pages::blog::Blog(page=1) },
+                Routes::BlogPage(page) => view! { pages::blog::Blog(page=page) },
+                Routes::BlogTag(tag) => view! { pages::blog::BlogTag(tag=tag) },
+                Routes::BlogAuthor(handle) => view! { pages::blog::BlogAuthor(handle=handle) },
                 Routes::BookSection(section) => view! { pages::book::Book(section=section) },
+                Routes::VersionedBookSection(version, section) => view! { pages::book::Book(version=version, section=section) },
                 Routes::BookSubsection(section, doc) => view! { pages::book::Book(section=section, doc=doc) },
+                Routes::VersionedBookSubsection(version, section, doc) => view! { pages::book::Book(version=version, section=section, doc=doc) },
                 Routes::NotFound =>  { pages::not_found::NotFound() },
             })
         }